@@ -0,0 +1,3870 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    fs::{self, File},
+    hash::{BuildHasher, Hasher},
+    path::{Component, Path, PathBuf},
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use brickadia::{read::SaveReader, save::SaveData};
+use chrono::{TimeZone, Utc};
+use omegga::{resources::Player, Omegga};
+use serde_json::Value;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    analyze_bricks, asset_breakdown, check_save,
+    config::{Config, Issue, LogLevel, THRESHOLDS},
+    connect_autosave, duration_text, export, finish_scanning, format_count, log,
+    messages::{escape_command_arg, Messages},
+    minigame_names, owner_roles, quarantine, read_rest, read_save, record_scan,
+    records::{
+        active_violations, find_by_name, get_actions, get_count, get_exempt, get_history, get_name,
+        get_pending, get_recent_clears, get_sighting, get_stats, get_timestamp, get_violations,
+        get_watched, is_dry_run, is_paused, log_action, set_allowed_assets, set_dry_run,
+        set_notifications, set_paused, set_recent_clears, set_thresholds, set_violations,
+        wants_notifications, Action, ActionKind, Violation,
+    },
+    save_now, timer_deadlines, timer_ids, ScanSummary, CONFIG_PATH, MESSAGES_PATH, REPORTING,
+    SCANNING, SCAN_SAVE_NAME, TIMER_PREFIXES,
+};
+
+/// Config keys that `/am config` only summarizes unless `full` is passed.
+const SUMMARIZED_CONFIG_KEYS: &[&str] = &["authorized", "exempt-players", "ignore-owners"];
+
+/// How long a `/am wipe` or `/am forgiveall` token can be confirmed for.
+const CONFIRM_TOKEN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The outstanding confirmation tokens: who asked for each, what it confirms, the token,
+/// and when it was issued.
+static CONFIRM_TOKENS: Mutex<Vec<(String, String, String, Instant)>> = Mutex::new(Vec::new());
+
+/// The most `/am setviolations` and `/am setbans` will set a count to.
+const MAX_SET_COUNT: usize = 1000;
+
+/// An `/am` subcommand.
+#[derive(Debug, Clone, Copy)]
+enum Subcommand {
+    Help,
+    Info,
+    Status,
+    Expires,
+    List,
+    Top,
+    History,
+    Stats,
+    Log,
+    Scan,
+    Check,
+    Find,
+    Goto,
+    Simulate,
+    Pause,
+    Resume,
+    DryRun,
+    Notify,
+    Pardon,
+    Restore,
+    Undo,
+    Quarantine,
+    SetViolations,
+    SetBans,
+    Unban,
+    ClearBans,
+    Exempt,
+    Permit,
+    Watch,
+    Unwatch,
+    AllowAsset,
+    Threshold,
+    Export,
+    Import,
+    Clean,
+    Wipe,
+    ForgiveAll,
+    Config,
+    Reload,
+}
+
+/// Every subcommand with its name, argument syntax, how many arguments it needs at least,
+/// and a one-line description.
+///
+/// Both the dispatcher and `/am help` read from this, so a subcommand missing here can't be run.
+/// Descriptions can be overridden in `messages.json` as `help-{name}`.
+const SUBCOMMANDS: &[(Subcommand, &str, &str, usize, &str)] = &[
+    (Subcommand::Help, "help", "", 0, "List every subcommand."),
+    (
+        Subcommand::Info,
+        "info",
+        "",
+        0,
+        "Show the plugin version, autosave connection, and last scan.",
+    ),
+    (
+        Subcommand::Status,
+        "status",
+        "(player)",
+        1,
+        "Show a player's timer, violations, and bans.",
+    ),
+    (
+        Subcommand::Expires,
+        "expires",
+        "(player) [tell]",
+        1,
+        "Show how long until a player's microbricks are cleared, telling them too with tell.",
+    ),
+    (
+        Subcommand::List,
+        "list",
+        "[page]",
+        0,
+        "List everyone with a timer, violations, or bans.",
+    ),
+    (
+        Subcommand::Top,
+        "top",
+        "[page]",
+        0,
+        "List the players with the most violations.",
+    ),
+    (
+        Subcommand::History,
+        "history",
+        "(player) [page]",
+        1,
+        "List a player's most recent clears.",
+    ),
+    (
+        Subcommand::Stats,
+        "stats",
+        "[reset]",
+        0,
+        "Show how much has been scanned and enforced.",
+    ),
+    (
+        Subcommand::Log,
+        "log",
+        "[count]",
+        0,
+        "Show the most recent warnings, clears, bans, and admin actions.",
+    ),
+    (
+        Subcommand::Scan,
+        "scan",
+        "",
+        0,
+        "Save the world and check it for microbricks right now.",
+    ),
+    (
+        Subcommand::Check,
+        "check",
+        "(player) [clear]",
+        1,
+        "Save the world and check one player's microbricks, clearing them if their timer is up.",
+    ),
+    (
+        Subcommand::Find,
+        "find",
+        "",
+        0,
+        "Save the world and list its microbricks by asset and owner, without enforcing anything.",
+    ),
+    (
+        Subcommand::Goto,
+        "goto",
+        "(player) [index]",
+        1,
+        "Teleport to where a flagged player's microbricks were last seen.",
+    ),
+    (
+        Subcommand::Simulate,
+        "simulate",
+        "(save)",
+        1,
+        "Report who has microbricks in a save from the Builds directory, without enforcing.",
+    ),
+    (
+        Subcommand::Pause,
+        "pause",
+        "",
+        0,
+        "Stop enforcing until resumed, even across restarts.",
+    ),
+    (
+        Subcommand::Resume,
+        "resume",
+        "",
+        0,
+        "Start enforcing again.",
+    ),
+    (
+        Subcommand::DryRun,
+        "dryrun",
+        "[on|off]",
+        0,
+        "Report what scans would do to admins instead of doing it.",
+    ),
+    (
+        Subcommand::Notify,
+        "notify",
+        "[on|off]",
+        0,
+        "Turn your admin notifications on or off.",
+    ),
+    (
+        Subcommand::Pardon,
+        "pardon",
+        "(player) [count]",
+        1,
+        "Remove a player's most recent violations.",
+    ),
+    (
+        Subcommand::Restore,
+        "restore",
+        "(player) [index]",
+        1,
+        "Load a player's removed microbricks back in, most recent first.",
+    ),
+    (
+        Subcommand::Undo,
+        "undo",
+        "",
+        0,
+        "Reverse the most recent clear, stepping further back each time.",
+    ),
+    (
+        Subcommand::Quarantine,
+        "quarantine",
+        "list [player] [page]|purge (days)",
+        1,
+        "Browse the saves of removed microbricks, numbered for restore, or delete old ones.",
+    ),
+    (
+        Subcommand::SetViolations,
+        "setviolations",
+        "(player) (count)",
+        2,
+        "Set how many violations a player has.",
+    ),
+    (
+        Subcommand::SetBans,
+        "setbans",
+        "(player) (count)",
+        2,
+        "Set how many times a player has been banned.",
+    ),
+    (
+        Subcommand::Unban,
+        "unban",
+        "(player) [reset]",
+        1,
+        "Lift a player's ban and take one ban off their count, or all of them with reset.",
+    ),
+    (
+        Subcommand::ClearBans,
+        "clearbans",
+        "(player)",
+        1,
+        "Reset a player's ban count, keeping their violations and timer.",
+    ),
+    (
+        Subcommand::Exempt,
+        "exempt",
+        "add|remove|list [player]",
+        1,
+        "Manage exempt players without editing the config.",
+    ),
+    (
+        Subcommand::Permit,
+        "permit",
+        "(player) (duration)",
+        2,
+        "Let a player use microbricks for a while, such as 2h or 30m.",
+    ),
+    (
+        Subcommand::Watch,
+        "watch",
+        "(player)",
+        1,
+        "Clear a player's microbricks on sight with no budget, grace period, or timer.",
+    ),
+    (
+        Subcommand::Unwatch,
+        "unwatch",
+        "(player)",
+        1,
+        "Stop watching a player.",
+    ),
+    (
+        Subcommand::AllowAsset,
+        "allowasset",
+        "add|remove|list [asset]",
+        1,
+        "Allow assets that would otherwise be banned, without editing the config.",
+    ),
+    (
+        Subcommand::Threshold,
+        "threshold",
+        "(name) (value)|reset (name)|list",
+        1,
+        "Override a detection setting at runtime, such as max-micro-count.",
+    ),
+    (
+        Subcommand::Export,
+        "export",
+        "",
+        0,
+        "Write every record to a JSON file, such as to move them to another server.",
+    ),
+    (
+        Subcommand::Import,
+        "import",
+        "(file) [replace]",
+        1,
+        "Load records from a file written by export, merging them unless replace is given.",
+    ),
+    (
+        Subcommand::Clean,
+        "clean",
+        "(player)|expired [days] [dry]",
+        1,
+        "Delete everything on a player's record, or every stale record.",
+    ),
+    (
+        Subcommand::Wipe,
+        "wipe",
+        "[confirm (token)]",
+        0,
+        "Delete every record, once confirmed.",
+    ),
+    (
+        Subcommand::ForgiveAll,
+        "forgiveall",
+        "[timers] [confirm (token)]",
+        0,
+        "Delete every violation record, and every timer with timers, once confirmed.",
+    ),
+    (
+        Subcommand::Config,
+        "config",
+        "[full]",
+        0,
+        "Show the active config.",
+    ),
+    (
+        Subcommand::Reload,
+        "reload",
+        "",
+        0,
+        "Reload the config from disk.",
+    ),
+];
+
+/// A player a subcommand acts on, who may or may not be online.
+pub struct Target {
+    pub id: String,
+    pub name: String,
+}
+
+/// Run an `/am` subcommand for an authorized player.
+pub async fn handle(
+    omegga: &Arc<Omegga>,
+    config: &Arc<RwLock<Config>>,
+    players: &[Player],
+    player: String,
+    args: Vec<String>,
+) {
+    let messages = config.read().await.messages.clone();
+
+    // a bare `/am` shows the help
+    let subcommand = args.first().map(String::as_str).unwrap_or("help");
+    let args = args.get(1..).unwrap_or_default();
+
+    let found = SUBCOMMANDS.iter().find(|(_, name, ..)| *name == subcommand);
+
+    // missing arguments get the subcommand's usage, rather than each subcommand guessing
+    if let Some((_, name, syntax, required, _)) = found {
+        if args.len() < *required {
+            let usage = format!("{} {}", name, syntax);
+            omegga.whisper(
+                player,
+                messages.text("command-usage", &[("usage", &usage.trim_end())]),
+            );
+            return;
+        }
+    }
+
+    let result = match found.map(|&(subcommand, ..)| subcommand) {
+        Some(Subcommand::Help) => {
+            help(omegga, &messages, player.clone());
+            Ok(())
+        }
+        Some(Subcommand::Info) => info(omegga, config, player.clone()).await,
+        Some(Subcommand::Status) => status(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Expires) => expires(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::List) => list(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Top) => top(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::History) => history(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Stats) => stats(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Log) => show_log(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Scan) => scan(omegga, config, player.clone()).await,
+        Some(Subcommand::Check) => check(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Find) => find(omegga, config, player.clone()).await,
+        Some(Subcommand::Goto) => goto(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Simulate) => simulate(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Pause) => pause(omegga, config, player.clone(), true).await,
+        Some(Subcommand::Resume) => pause(omegga, config, player.clone(), false).await,
+        Some(Subcommand::DryRun) => dry_run(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Notify) => notify(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Pardon) => pardon(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Restore) => restore(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Undo) => undo(omegga, config, player.clone()).await,
+        Some(Subcommand::Quarantine) => {
+            show_quarantine(omegga, config, players, player.clone(), args).await
+        }
+        Some(Subcommand::SetViolations) => {
+            set_count(omegga, config, players, player.clone(), args, false).await
+        }
+        Some(Subcommand::SetBans) => {
+            set_count(omegga, config, players, player.clone(), args, true).await
+        }
+        Some(Subcommand::Unban) => unban(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::ClearBans) => {
+            clear_bans(omegga, config, players, player.clone(), args).await
+        }
+        Some(Subcommand::Exempt) => exempt(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Permit) => permit(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Watch) => watch(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Unwatch) => unwatch(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::AllowAsset) => allow_asset(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Threshold) => threshold(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Export) => export(omegga, config, player.clone()).await,
+        Some(Subcommand::Import) => import(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Clean) => clean(omegga, config, players, player.clone(), args).await,
+        Some(Subcommand::Wipe) => wipe(omegga, config, player.clone(), args).await,
+        Some(Subcommand::ForgiveAll) => forgive_all(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Config) => show_config(omegga, config, player.clone(), args).await,
+        Some(Subcommand::Reload) => {
+            reload(omegga, config, player.clone()).await;
+            Ok(())
+        }
+        None => {
+            omegga.whisper(
+                player.clone(),
+                messages.text("command-invalid", &[("subcommand", &subcommand)]),
+            );
+            Ok(())
+        }
+    };
+
+    if let Err(e) = result {
+        omegga.error(format!("failed to run /am {}: {}", subcommand, e));
+        omegga.whisper(player, messages.text("command-failed", &[("error", &e)]));
+    }
+}
+
+/// `/am help`: list every subcommand.
+fn help(omegga: &Omegga, messages: &Messages, player: String) {
+    for (_, name, args, _, description) in SUBCOMMANDS {
+        let usage = format!("{} {}", name, args);
+        omegga.whisper(
+            player.clone(),
+            messages.text(
+                "command-help-row",
+                &[
+                    ("usage", &usage.trim_end()),
+                    (
+                        "description",
+                        &messages.get_or(&format!("help-{}", name), description),
+                    ),
+                ],
+            ),
+        );
+    }
+}
+
+/// `/am info`: show the plugin version, whether an autosave plugin is connected,
+/// when the last save came in, how the last scan went, and whether enforcement is on.
+async fn info(omegga: &Omegga, config: &RwLock<Config>, player: String) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let status = crate::status();
+    let now = Utc::now().timestamp() as u64;
+    let ago = |time: u64| ago_text(messages, now.saturating_sub(time));
+
+    let mut lines = vec![messages.text("command-info", &[("version", &env!("CARGO_PKG_VERSION"))])];
+
+    lines.push(match status.connected {
+        Some((plugin, time)) => messages.text(
+            "command-info-connected",
+            &[("plugin", &plugin), ("ago", &ago(time))],
+        ),
+        None => messages.text(
+            "command-info-not-connected",
+            &[("plugins", &config.autosave_plugins.join(", "))],
+        ),
+    });
+
+    lines.push(match status.last_emit {
+        Some(time) => messages.text("command-info-last-emit", &[("ago", &ago(time))]),
+        None => messages.get("command-info-no-emit").into(),
+    });
+
+    lines.push(match status.last_scan {
+        Some((time, Ok(summary))) => messages.text(
+            "command-info-last-scan",
+            &[
+                ("ago", &ago(time)),
+                ("flagged", &summary.flagged),
+                ("cleared", &summary.cleared),
+                ("removed", &summary.removed),
+            ],
+        ),
+        Some((time, Err(e))) => messages.text(
+            "command-info-last-scan-failed",
+            &[("ago", &ago(time)), ("error", &e)],
+        ),
+        None => messages.get("command-info-no-scan").into(),
+    });
+
+    lines.push(
+        messages
+            .get(if is_paused(omegga).await? {
+                "command-info-paused"
+            } else if is_dry_run(omegga).await? {
+                "command-info-dry-run"
+            } else {
+                "command-info-enforcing"
+            })
+            .into(),
+    );
+
+    for line in lines {
+        omegga.whisper(player.clone(), line);
+    }
+    Ok(())
+}
+
+/// Find who a subcommand should act on: an online player by name prefix,
+/// or anyone by id or by their last known name.
+///
+/// If no one matches, the `missing` message is whispered to the player.
+/// If more than one player matches, they're listed instead of guessing.
+pub async fn resolve_target(
+    omegga: &Omegga,
+    messages: &Messages,
+    players: &[Player],
+    player: &str,
+    query: &str,
+    missing: &str,
+) -> Result<Option<Target>> {
+    let mut candidates = resolve_candidates(omegga, players, query).await?;
+    match candidates.len() {
+        0 => omegga.whisper(player, messages.get(missing)),
+        1 => return Ok(candidates.pop()),
+        _ => {
+            let names = candidates
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            omegga.whisper(
+                player,
+                messages.text("command-ambiguous-player", &[("players", &names)]),
+            );
+        }
+    }
+
+    Ok(None)
+}
+
+/// Find everyone a query could refer to, trying online players first,
+/// then an id, then anyone whose last known name matches or starts with the query.
+///
+/// A full name match always wins over names it's only a prefix of.
+async fn resolve_candidates(
+    omegga: &Omegga,
+    players: &[Player],
+    query: &str,
+) -> Result<Vec<Target>> {
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let online = match_online(players, query);
+    if !online.is_empty() {
+        return Ok(online
+            .into_iter()
+            .map(|p| Target {
+                id: p.id.clone(),
+                name: p.name.clone(),
+            })
+            .collect());
+    }
+
+    if let Ok(id) = query.parse::<Uuid>() {
+        let id = id.to_string();
+        let name = get_name(omegga, &id).await?.unwrap_or_else(|| id.clone());
+        return Ok(vec![Target { id, name }]);
+    }
+
+    Ok(find_by_name(omegga, query)
+        .await?
+        .into_iter()
+        .map(|(id, name)| Target { id, name })
+        .collect())
+}
+
+/// Find the online players a query could refer to by name, ignoring case:
+/// the one whose full name it is, otherwise everyone whose name starts with it.
+fn match_online<'a>(players: &'a [Player], query: &str) -> Vec<&'a Player> {
+    let lower = query.to_lowercase();
+    match players.iter().find(|p| p.name.to_lowercase() == lower) {
+        Some(p) => vec![p],
+        None => players
+            .iter()
+            .filter(|p| p.name.to_lowercase().starts_with(&lower))
+            .collect(),
+    }
+}
+
+/// `/am clean <player>`: delete everything on a player's record.
+async fn clean(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    if args.first().map(String::as_str) == Some("expired") {
+        return clean_expired(omegga, &config, player, &args[1..]).await;
+    }
+
+    let messages = &config.messages;
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &args.join(" "),
+        "command-clean-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    for prefix in [
+        "violations",
+        "history",
+        "bans",
+        "kicks",
+        "sighting",
+        "watch",
+    ] {
+        omegga
+            .store_delete(format!("{}:{}", prefix, target.id))
+            .await;
+    }
+
+    // timers are kept per category, as well as for microbricks
+    for timer in timer_ids(&config, target.id) {
+        for prefix in TIMER_PREFIXES.iter().chain(&["lastclear"]) {
+            omegga.store_delete(format!("{}:{}", prefix, timer)).await;
+        }
+    }
+    log_action(
+        omegga,
+        Action::now(ActionKind::Clean, &target.name, 0).by(&player),
+    )
+    .await?;
+
+    omegga.whisper(
+        player,
+        messages.text("command-clean", &[("player", &target.name)]),
+    );
+    Ok(())
+}
+
+/// The records `/am clean expired` drops for players who haven't been seen in a while.
+const STALE_RECORD_PREFIXES: &[&str] = &[
+    "violations",
+    "history",
+    "bans",
+    "kicks",
+    "lastclear",
+    "sighting",
+    "watch",
+];
+
+/// `/am clean expired [days] [dry]`: delete timers older than `days`,
+/// and the records of players who haven't been seen in that long.
+///
+/// Players seen before last-seen times were recorded are left alone.
+/// With `dry`, records are only counted.
+async fn clean_expired(
+    omegga: &Omegga,
+    config: &Config,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let messages = &config.messages;
+    let dry = args.last().map(String::as_str) == Some("dry");
+    let days = match args.first().filter(|arg| *arg != "dry") {
+        Some(arg) => match arg.parse::<f32>() {
+            Ok(days) if days > 0. => days,
+            _ => {
+                omegga.whisper(
+                    player,
+                    messages.text("command-clean-expired-invalid", &[("days", arg)]),
+                );
+                return Ok(());
+            }
+        },
+        None => config.stale_record_days,
+    };
+
+    let now = Utc::now().timestamp() as u64;
+    let cutoff = now.saturating_sub((days * 60. * 60. * 24.) as u64);
+
+    let keys = omegga.store_keys().await?;
+    let mut stale = vec![];
+    for key in keys.iter() {
+        // timers (and their pending clears) that have been running for too long
+        if let Some(id) = key.strip_prefix("ts:") {
+            if get_timestamp(omegga, key.clone()).await?.unwrap_or(0) < cutoff {
+                stale.push(key.clone());
+                stale.push(format!("pending:{}", id));
+                stale.push(format!("hidden:{}", id));
+            }
+            continue;
+        }
+
+        // and the records of anyone who hasn't been around
+        let id = match key.strip_prefix("lastseen:") {
+            Some(id) => id,
+            None => continue,
+        };
+        if get_timestamp(omegga, key.clone()).await?.unwrap_or(0) >= cutoff {
+            continue;
+        }
+        for prefix in STALE_RECORD_PREFIXES {
+            stale.push(format!("{}:{}", prefix, id));
+        }
+    }
+
+    stale.sort();
+    stale.dedup();
+    stale.retain(|key| keys.contains(key));
+
+    if !dry {
+        for key in stale.iter() {
+            omegga.store_delete(key.clone()).await;
+        }
+
+        log(
+            omegga,
+            config,
+            LogLevel::Normal,
+            format!(
+                "{} cleaned {} records older than {} days",
+                player,
+                stale.len(),
+                days
+            ),
+        );
+    }
+
+    let key = if dry {
+        "command-clean-expired-dry"
+    } else {
+        "command-clean-expired"
+    };
+    omegga.whisper(
+        player,
+        messages.text(key, &[("count", &stale.len()), ("days", &days)]),
+    );
+    Ok(())
+}
+
+/// `/am export`: write every record in the store to a timestamped JSON file in the background,
+/// whispering where it went once it's done.
+async fn export(omegga: &Arc<Omegga>, config: &Arc<RwLock<Config>>, player: String) -> Result<()> {
+    let messages = config.read().await.messages.clone();
+    omegga.whisper(player.clone(), messages.get("command-export-started"));
+
+    let (omegga, config) = (Arc::clone(omegga), Arc::clone(config));
+    tokio::spawn(async move {
+        let result = export_now(&omegga).await;
+
+        let config = config.read().await;
+        match result {
+            Ok((count, path)) => {
+                let path = path.display();
+                log(
+                    &omegga,
+                    &config,
+                    LogLevel::Normal,
+                    format!("{} exported {} records to {}", player, count, path),
+                );
+                omegga.whisper(
+                    player,
+                    config
+                        .messages
+                        .text("command-export", &[("count", &count), ("path", &path)]),
+                );
+            }
+            Err(e) => {
+                omegga.error(format!("failed to run /am export: {}", e));
+                omegga.whisper(
+                    player,
+                    config.messages.text("command-failed", &[("error", &e)]),
+                );
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Export every record to a new file, returning how many there were and where they went.
+async fn export_now(omegga: &Omegga) -> Result<(usize, PathBuf)> {
+    let records = export::collect(omegga).await?;
+    let path = export::write(&records).await?;
+    Ok((records.records.len(), path))
+}
+
+/// `/am import <file> [replace]`: load records from a file in the exports directory
+/// in the background, merging them with the existing records unless `replace` is passed.
+async fn import(
+    omegga: &Arc<Omegga>,
+    config: &Arc<RwLock<Config>>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let (messages, allow_wipe) = {
+        let config = config.read().await;
+        (config.messages.clone(), config.allow_wipe)
+    };
+    let (name, replace) = match args.split_last() {
+        Some((last, rest)) if last == "replace" && !rest.is_empty() => (rest.join(" "), true),
+        _ => (args.join(" "), false),
+    };
+
+    // replacing wipes every record first, so it's held to the same setting as `/am wipe`
+    if replace && !allow_wipe {
+        omegga.whisper(player, messages.get("command-wipe-disabled"));
+        return Ok(());
+    }
+
+    let path = match contained_path(export::EXPORTS_DIR, &name, "json") {
+        Some(path) if path.is_file() => path,
+        _ => {
+            omegga.whisper(
+                player,
+                messages.text("command-import-missing", &[("file", &name)]),
+            );
+            return Ok(());
+        }
+    };
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("command-import-started", &[("file", &name)]),
+    );
+
+    let (omegga, config) = (Arc::clone(omegga), Arc::clone(config));
+    tokio::spawn(async move {
+        let result = import_now(&omegga, &path, replace).await;
+
+        let config = config.read().await;
+        let messages = &config.messages;
+        match result {
+            Ok(imported) if imported.is_empty() => {
+                omegga.whisper(player, messages.get("command-import-none"))
+            }
+            Ok(imported) => {
+                let counts = imported
+                    .iter()
+                    .map(|(kind, count)| format!("{} {}", count, kind))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                log(
+                    &omegga,
+                    &config,
+                    LogLevel::Normal,
+                    format!(
+                        "{} imported records from {} ({}, {})",
+                        player,
+                        path.display(),
+                        if replace { "replaced" } else { "merged" },
+                        counts
+                    ),
+                );
+                omegga.whisper(
+                    player,
+                    messages.text(
+                        "command-import",
+                        &[
+                            ("count", &imported.values().sum::<usize>()),
+                            ("records", &counts),
+                        ],
+                    ),
+                );
+            }
+            Err(e) => omegga.whisper(
+                player,
+                messages.text("command-import-invalid", &[("error", &e)]),
+            ),
+        }
+    });
+
+    Ok(())
+}
+
+/// Read and validate an export, then write its records into the store.
+///
+/// Nothing is written if the file is malformed.
+async fn import_now(
+    omegga: &Omegga,
+    path: &Path,
+    replace: bool,
+) -> Result<BTreeMap<String, usize>> {
+    let records = export::read(path).await?;
+    export::import(omegga, records, replace).await
+}
+
+/// `/am wipe`, then `/am wipe confirm <token>`: delete every record.
+async fn wipe(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    if !config.allow_wipe {
+        omegga.whisper(player, config.messages.get("command-wipe-disabled"));
+        return Ok(());
+    }
+
+    let messages = &config.messages;
+    let token = match (args.first().map(String::as_str), args.get(1)) {
+        (Some("confirm"), Some(token)) => token,
+        _ => {
+            let token = issue_token(&player, "wipe");
+            omegga.whisper(
+                player,
+                messages.text(
+                    "command-wipe-confirm",
+                    &[
+                        ("token", &token),
+                        ("seconds", &CONFIRM_TOKEN_TIMEOUT.as_secs()),
+                    ],
+                ),
+            );
+            return Ok(());
+        }
+    };
+
+    if let Err(reason) = redeem_token(&player, "wipe", token) {
+        omegga.whisper(player, messages.get(&format!("command-wipe-{}", reason)));
+        return Ok(());
+    }
+
+    omegga.store_wipe();
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!("{} wiped all records", player),
+    );
+    omegga.whisper(player, messages.get("command-wipe"));
+    Ok(())
+}
+
+/// `/am forgiveall [timers]`, then `/am forgiveall [timers] confirm <token>`: delete every
+/// violation record, and every timer too with `timers`, leaving bans, exemptions, and stats alone.
+async fn forgive_all(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (args, token) = match args {
+        [rest @ .., confirm, token] if confirm == "confirm" => (rest, Some(token)),
+        _ => (args, None),
+    };
+    let timers = match args {
+        [] => false,
+        [flag] if flag == "timers" => true,
+        _ => {
+            omegga.whisper(player, messages.get("command-forgiveall-usage"));
+            return Ok(());
+        }
+    };
+
+    // the token is tied to the flag, so confirming can't forgive more than was asked for
+    let action = if timers {
+        "forgiveall timers"
+    } else {
+        "forgiveall"
+    };
+    let token = match token {
+        Some(token) => token,
+        None => {
+            let token = issue_token(&player, action);
+            omegga.whisper(
+                player,
+                messages.text(
+                    "command-forgiveall-confirm",
+                    &[
+                        ("command", &action),
+                        ("token", &token),
+                        ("seconds", &CONFIRM_TOKEN_TIMEOUT.as_secs()),
+                    ],
+                ),
+            );
+            return Ok(());
+        }
+    };
+
+    if let Err(reason) = redeem_token(&player, action, token) {
+        omegga.whisper(
+            player,
+            messages.get(&format!("command-forgiveall-{}", reason)),
+        );
+        return Ok(());
+    }
+
+    // a pending clear is only ever waiting on a timer, so it goes with them
+    let (mut violations, mut reset_timers) = (0, 0);
+    for key in omegga.store_keys().await? {
+        if key.starts_with("violations:") {
+            violations += 1;
+        } else if timers && key.starts_with("ts:") {
+            reset_timers += 1;
+        } else if !(timers && (key.starts_with("pending:") || key.starts_with("hidden:"))) {
+            continue;
+        }
+        omegga.store_delete(key).await;
+    }
+
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} forgave all violations ({} records) and reset {} timers",
+            player, violations, reset_timers
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-forgiveall",
+            &[("violations", &violations), ("timers", &reset_timers)],
+        ),
+    );
+    Ok(())
+}
+
+/// Give an admin a fresh token to confirm `action` with, replacing any they were already given.
+fn issue_token(player: &str, action: &str) -> String {
+    // each admin gets their own token, so nobody can confirm someone else's action
+    let token = format!(
+        "{:06x}",
+        RandomState::new().build_hasher().finish() & 0xff_ffff
+    );
+    let mut tokens = CONFIRM_TOKENS.lock().unwrap();
+    tokens.retain(|(admin, ..)| admin != player);
+    tokens.push((
+        player.to_string(),
+        action.to_string(),
+        token.clone(),
+        Instant::now(),
+    ));
+    token
+}
+
+/// Use up an admin's token for `action`, or say why it can't be:
+/// `mismatch`, `expired`, or `no-token`, to be looked up under the subcommand's messages.
+fn redeem_token(player: &str, action: &str, token: &str) -> Result<(), &'static str> {
+    let mut tokens = CONFIRM_TOKENS.lock().unwrap();
+    let pending = tokens
+        .iter()
+        .position(|(admin, pending, ..)| admin == player && pending == action);
+    match pending {
+        Some(i) if tokens[i].2 != token && tokens[i].3.elapsed() < CONFIRM_TOKEN_TIMEOUT => {
+            Err("mismatch")
+        }
+        Some(i) => {
+            let (.., issued) = tokens.remove(i);
+            if issued.elapsed() < CONFIRM_TOKEN_TIMEOUT {
+                Ok(())
+            } else {
+                Err("expired")
+            }
+        }
+        None => Err("no-token"),
+    }
+}
+
+/// `/am reload`: re-read the config from disk and swap it in,
+/// keeping the old config if the new one is invalid.
+async fn reload(omegga: &Omegga, config: &RwLock<Config>, player: String) {
+    // anything said before the new config is swapped in uses the old config's messages
+    let messages = config.read().await.messages.clone();
+    let mut new_config = match Config::load(CONFIG_PATH, MESSAGES_PATH) {
+        Ok(c) => c,
+        Err(e) => {
+            omegga.whisper(player, messages.text("reload-failed", &[("error", &e)]));
+            return;
+        }
+    };
+
+    let issues = new_config.validate();
+    let fatal = issues.iter().any(Issue::is_fatal);
+    if fatal {
+        omegga.whisper(player.clone(), messages.get("reload-invalid"));
+    }
+
+    for issue in issues.iter() {
+        match issue {
+            Issue::Fatal(e) => omegga.whisper(
+                player.clone(),
+                messages.text("reload-error", &[("error", e)]),
+            ),
+            Issue::Warning(w) => omegga.whisper(
+                player.clone(),
+                messages.text("reload-warning", &[("warning", w)]),
+            ),
+        }
+    }
+
+    if fatal {
+        return;
+    }
+
+    let mut config = config.write().await;
+    new_config.set_runtime_allowed_assets(config.runtime_allowed_assets.clone());
+    for e in new_config.apply_thresholds(&config.runtime_thresholds) {
+        omegga.whisper(
+            player.clone(),
+            messages.text("reload-warning", &[("warning", &e)]),
+        );
+    }
+    let changes = config.diff(&new_config);
+    let reconnect = config.autosave_plugins != new_config.autosave_plugins;
+    *config = new_config;
+
+    if reconnect {
+        connect_autosave(omegga, &config).await;
+    }
+
+    let messages = &config.messages;
+    if changes.is_empty() {
+        omegga.whisper(player, messages.get("reload-unchanged"));
+        return;
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("reload-changed", &[("count", &changes.len())]),
+    );
+    for change in changes {
+        omegga.whisper(player.clone(), change);
+    }
+    log(omegga, &config, LogLevel::Normal, "Config reloaded");
+}
+
+/// `/am status <player>`: summarize a player's timer, violations, and bans.
+async fn status(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &args.join(" "),
+        "command-status-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let deadlines = timer_deadlines(omegga, &config, players, &target.id).await?;
+    let pending = get_pending(omegga, &target.id).await?;
+    let violations = get_violations(omegga, &target.id).await?;
+    let bans = get_count(omegga, format!("bans:{}", target.id)).await?;
+
+    // expired permits linger until the next scan, but no longer count
+    let now = Utc::now().timestamp() as u64;
+    let permit = get_timestamp(omegga, format!("permit:{}", target.id))
+        .await?
+        .filter(|&expires| now < expires);
+    let watched = omegga
+        .store_get(format!("watch:{}", target.id))
+        .await?
+        .is_some();
+    let sighting = get_sighting(omegga, &target.id)
+        .await?
+        .filter(|sighting| !sighting.assets.is_empty());
+
+    if deadlines.is_empty()
+        && pending.is_none()
+        && sighting.is_none()
+        && violations.is_empty()
+        && bans == 0
+        && permit.is_none()
+        && !watched
+    {
+        omegga.whisper(
+            player,
+            messages.text("command-status-none", &[("player", &target.name)]),
+        );
+        return Ok(());
+    }
+
+    let mut lines = vec![messages.text(
+        "command-status",
+        &[("player", &target.name), ("id", &target.id)],
+    )];
+    if is_paused(omegga).await? {
+        lines.push(messages.get("command-paused-notice").into());
+    }
+
+    if watched {
+        lines.push(messages.get("command-status-watched").into());
+    }
+
+    if let Some(expires) = permit {
+        lines.push(messages.text(
+            "command-status-permit",
+            &[
+                (
+                    "duration",
+                    &duration_text(messages, ((expires - now) as f32 / 60.).ceil()),
+                ),
+                ("time", &format_time(expires)),
+            ],
+        ));
+    }
+
+    if deadlines.is_empty() {
+        lines.push(messages.get("command-status-no-timer").into());
+    }
+    for &(category, clear_at) in deadlines.iter() {
+        let minutes = (clear_at.saturating_sub(now) as f32 / 60.).ceil();
+        lines.push(match category {
+            Some(category) => messages.text(
+                "command-status-category-timer",
+                &[
+                    ("category", &config.parsed_categories[category].name),
+                    ("minutes", &minutes),
+                ],
+            ),
+            None => messages.text("command-status-timer", &[("minutes", &minutes)]),
+        });
+    }
+
+    // what the last scan to flag them found
+    if let Some(sighting) = sighting {
+        let assets = asset_breakdown(
+            messages,
+            sighting
+                .assets
+                .iter()
+                .map(|(asset, count)| (asset.as_str(), *count)),
+        );
+        lines.push(messages.text(
+            "command-status-assets",
+            &[
+                (
+                    "ago",
+                    &ago_text(messages, now.saturating_sub(sighting.time)),
+                ),
+                ("assets", &assets),
+            ],
+        ));
+    }
+
+    if let Some(pending) = pending {
+        lines.push(messages.text(
+            "command-status-pending",
+            &[
+                ("scans", &pending.scans),
+                ("required", &config.confirmation_scans),
+            ],
+        ));
+    }
+
+    lines.push(messages.text(
+        "command-status-violations",
+        &[
+            (
+                "violations",
+                &active_violations(&violations, config.violation_expiry_days),
+            ),
+            ("max", &config.max_violations),
+        ],
+    ));
+    lines.push(messages.text(
+        "command-status-bans",
+        &[("bans", &bans), ("max", &config.max_bans)],
+    ));
+
+    for line in lines {
+        omegga.whisper(player.clone(), line);
+    }
+    Ok(())
+}
+
+/// `/am expires <player> [tell]`: show how long until a player's microbricks are cleared,
+/// whispering it to them too if `tell` is passed and they're online.
+async fn expires(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, tell) = match args.split_last() {
+        Some((last, rest)) if last == "tell" && !rest.is_empty() => (rest.join(" "), true),
+        _ => (args.join(" "), false),
+    };
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-expires-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    // each timer runs out the same way it would in a scan, role overrides included
+    let deadlines = timer_deadlines(omegga, &config, players, &target.id).await?;
+    if deadlines.is_empty() {
+        omegga.whisper(
+            player,
+            messages.text("command-expires-none", &[("player", &target.name)]),
+        );
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp() as u64;
+    let online = players.iter().find(|p| p.id == target.id).filter(|_| tell);
+    for (category, clear_at) in deadlines {
+        // categories have their own messages, like `command-expires-category-passed`
+        let category = category.map(|category| config.parsed_categories[category].name.as_str());
+        let suffix = match (category.is_some(), now >= clear_at) {
+            (false, false) => "",
+            (false, true) => "-passed",
+            (true, false) => "-category",
+            (true, true) => "-category-passed",
+        };
+        let remaining = remaining_text(clear_at.saturating_sub(now));
+        let vars: &[(&str, &dyn Display)] = &[
+            ("player", &target.name),
+            ("category", &category.unwrap_or_default()),
+            ("remaining", &remaining),
+        ];
+
+        omegga.whisper(
+            player.clone(),
+            messages.text(&format!("command-expires{}", suffix), vars),
+        );
+        if let Some(p) = online {
+            omegga.whisper(
+                p.name.clone(),
+                messages.text(&format!("command-expires-target{}", suffix), vars),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Describe a short remaining time in minutes and seconds, like `7m 30s`.
+fn remaining_text(seconds: u64) -> String {
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{}s", seconds),
+        (minutes, 0) => format!("{}m", minutes),
+        (minutes, seconds) => format!("{}m {}s", minutes, seconds),
+    }
+}
+
+/// `/am list [page]`: summarize everyone with a timer, violations, or bans, most urgent first.
+async fn list(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let number = page_number(args.first());
+
+    let ids = omegga
+        .store_keys()
+        .await?
+        .iter()
+        .filter_map(|key| {
+            key.strip_prefix("ts:")
+                .or_else(|| key.strip_prefix("violations:"))
+                .or_else(|| key.strip_prefix("bans:"))
+                .or_else(|| key.strip_prefix("watch:"))
+                // category timers are keyed like `ts:lights:{id}`
+                .and_then(|id| id.rsplit(':').next())
+                .map(String::from)
+        })
+        .collect::<BTreeSet<_>>();
+    let watched = get_watched(omegga).await?;
+
+    if is_paused(omegga).await? {
+        omegga.whisper(player.clone(), messages.get("command-paused-notice"));
+    }
+
+    if ids.is_empty() {
+        omegga.whisper(player, messages.get("command-list-empty"));
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp() as u64;
+    let mut rows = vec![];
+    for id in ids.iter() {
+        let mut name = players
+            .iter()
+            .find(|p| &p.id == id)
+            .map(|p| p.name.clone())
+            .unwrap_or_else(|| id.clone());
+        if id.parse().is_ok_and(|id| watched.contains_key(&id)) {
+            name.push_str(messages.get("command-list-watched"));
+        }
+
+        let remaining = timer_deadlines(omegga, &config, players, id)
+            .await?
+            .into_iter()
+            .map(|(category, clear_at)| (category, clear_at.saturating_sub(now)))
+            .collect::<Vec<_>>();
+
+        let violations = active_violations(
+            &get_violations(omegga, id).await?,
+            config.violation_expiry_days,
+        );
+        let bans = get_count(omegga, format!("bans:{}", id)).await?;
+        rows.push((name, remaining, violations, bans));
+    }
+
+    // players with timers come first, soonest to be cleared at the top
+    rows.sort_by_key(|(_, remaining, _, _)| {
+        remaining
+            .iter()
+            .map(|&(_, seconds)| seconds)
+            .min()
+            .unwrap_or(u64::MAX)
+    });
+
+    let page = match paginate(omegga, &config, &player, &rows, number) {
+        Some(page) => page,
+        None => return Ok(()),
+    };
+    for (name, remaining, violations, bans) in page.rows.iter() {
+        let timer = if remaining.is_empty() {
+            messages.get("command-list-no-timer").into()
+        } else {
+            remaining
+                .iter()
+                .map(|&(category, seconds)| {
+                    let minutes = (seconds as f32 / 60.).ceil();
+                    match category {
+                        Some(category) => messages.text(
+                            "command-list-category-timer",
+                            &[
+                                ("category", &config.parsed_categories[category].name),
+                                ("minutes", &minutes),
+                            ],
+                        ),
+                        None => messages.text("command-list-timer", &[("minutes", &minutes)]),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        omegga.whisper(
+            player.clone(),
+            messages.text(
+                "command-list-row",
+                &[
+                    ("player", name),
+                    ("timer", &timer),
+                    ("violations", violations),
+                    ("bans", bans),
+                ],
+            ),
+        );
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("command-list-total", &[("count", &rows.len())]),
+    );
+    whisper_page_footer(omegga, messages, &player, &page, "list");
+    Ok(())
+}
+
+/// `/am scan`: save the world right now and check it in the background,
+/// whispering what was found once it's done.
+async fn scan(omegga: &Arc<Omegga>, config: &Arc<RwLock<Config>>, player: String) -> Result<()> {
+    let messages = config.read().await.messages.clone();
+    if is_paused(omegga).await? {
+        omegga.whisper(player, messages.get("command-paused-notice"));
+        return Ok(());
+    }
+
+    if SCANNING.swap(true, Ordering::SeqCst) {
+        omegga.whisper(player, messages.get("command-scan-busy"));
+        return Ok(());
+    }
+
+    omegga.whisper(player.clone(), messages.get("command-scan-started"));
+
+    let (omegga, config) = (Arc::clone(omegga), Arc::clone(config));
+    tokio::spawn(async move {
+        let result = scan_now(&omegga, &config).await;
+
+        // (reporting before letting go of the scan, since an autosave may be waiting on it)
+        let messages = config.read().await.messages.clone();
+        match result {
+            Ok(summary) if summary.flagged == 0 && summary.cleared == 0 && summary.removed == 0 => {
+                omegga.whisper(player, messages.get("command-scan-clean"))
+            }
+            Ok(summary) => omegga.whisper(
+                player,
+                messages.text(
+                    "command-scan-result",
+                    &[
+                        ("flagged", &summary.flagged),
+                        ("cleared", &summary.cleared),
+                        ("removed", &summary.removed),
+                    ],
+                ),
+            ),
+            Err(e) => {
+                omegga.error(format!("failed to run /am scan: {}", e));
+                omegga.whisper(player, messages.text("command-failed", &[("error", &e)]));
+            }
+        }
+        finish_scanning(&omegga, &config).await;
+    });
+
+    Ok(())
+}
+
+/// Have the game write a fresh save, then check it.
+async fn scan_now(omegga: &Omegga, config: &RwLock<Config>) -> Result<ScanSummary> {
+    // (against a copy of the config, so commands that change it don't wait on the scan)
+    let config = config.read().await.clone();
+    let path = save_now(omegga, &config, SCAN_SAVE_NAME).await?;
+
+    let summary = check_save(omegga, &config, path.clone(), None).await;
+    record_scan(&summary);
+    let _ = fs::remove_file(&path);
+    summary
+}
+
+/// `/am check <player> [clear]`: save the world right now and report one player's microbricks
+/// in the background, clearing only them if `clear` is passed and their timer is up.
+async fn check(
+    omegga: &Arc<Omegga>,
+    config: &Arc<RwLock<Config>>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let messages = config.read().await.messages.clone();
+    let (query, clear) = match args.split_last() {
+        Some((last, rest)) if last == "clear" && !rest.is_empty() => (rest.join(" "), true),
+        _ => (args.join(" "), false),
+    };
+
+    let target = match resolve_target(
+        omegga,
+        &messages,
+        players,
+        &player,
+        &query,
+        "command-check-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+    let id = match target.id.parse::<Uuid>() {
+        Ok(id) => id,
+        Err(_) => {
+            omegga.whisper(player, messages.get("command-check-no-player"));
+            return Ok(());
+        }
+    };
+
+    if clear && is_paused(omegga).await? {
+        omegga.whisper(player, messages.get("command-paused-notice"));
+        return Ok(());
+    }
+
+    // only a check that can clear holds up autosave checks
+    let flag = if clear { &SCANNING } else { &REPORTING };
+    if flag.swap(true, Ordering::SeqCst) {
+        omegga.whisper(player, messages.get("command-scan-busy"));
+        return Ok(());
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("command-check-started", &[("player", &target.name)]),
+    );
+
+    let (omegga, config) = (Arc::clone(omegga), Arc::clone(config));
+    tokio::spawn(async move {
+        let result = check_now(&omegga, &config, &player, &target, id, clear).await;
+        if let Err(e) = result {
+            let messages = config.read().await.messages.clone();
+            omegga.error(format!("failed to run /am check: {}", e));
+            omegga.whisper(player, messages.text("command-failed", &[("error", &e)]));
+        }
+
+        if clear {
+            finish_scanning(&omegga, &config).await;
+        } else {
+            REPORTING.store(false, Ordering::SeqCst);
+        }
+    });
+
+    Ok(())
+}
+
+/// Have the game write a fresh save, then report on one owner's microbricks in it.
+async fn check_now(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: &str,
+    target: &Target,
+    id: Uuid,
+    clear: bool,
+) -> Result<()> {
+    let config = config.read().await.clone();
+    let path = save_now(omegga, &config, SCAN_SAVE_NAME).await?;
+
+    let result = check_owner(omegga, &config, player, target, id, clear, &path).await;
+    let _ = fs::remove_file(&path);
+    result
+}
+
+async fn check_owner(
+    omegga: &Omegga,
+    config: &Config,
+    player: &str,
+    target: &Target,
+    id: Uuid,
+    clear: bool,
+    path: &Path,
+) -> Result<()> {
+    let messages = &config.messages;
+
+    // count up the owner's microbricks exactly the way a scan would, leaving everyone else's alone
+    let save = read_save(path).await?;
+    let minigames = if config.skip_minigame_owners {
+        minigame_names(omegga).await
+    } else {
+        vec![]
+    };
+    let analysis = analyze_bricks(config, &save.header2, &save.bricks, Some(id), &minigames);
+    let micros = match analysis.owners.get(&id) {
+        Some(micros) => micros,
+        None => {
+            omegga.whisper(
+                player,
+                messages.text("command-check-clean", &[("player", &target.name)]),
+            );
+            return Ok(());
+        }
+    };
+    let count = micros.count;
+
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-check",
+            &[
+                ("player", &target.name),
+                ("count", &count),
+                ("max", &config.max_micro_count),
+            ],
+        ),
+    );
+
+    let mut assets = micros.assets.iter().collect::<Vec<_>>();
+    assets.sort_by_key(|&(asset, count)| (std::cmp::Reverse(*count), *asset));
+    for (asset, count) in assets {
+        omegga.whisper(
+            player,
+            messages.text("command-check-asset", &[("asset", asset), ("count", count)]),
+        );
+    }
+
+    // along with whether a scan would act on them at all
+    match micros.threshold(config) {
+        Some((threshold, over)) => omegga.whisper(
+            player,
+            messages.text(
+                "command-check-over",
+                &[("reason", &threshold.describe(config, micros, over))],
+            ),
+        ),
+        None => omegga.whisper(player, messages.get("command-check-within")),
+    }
+
+    // the timer runs out the same way it would in a full scan, role overrides included
+    let players = omegga.get_players().await?;
+    let roles = owner_roles(omegga, &players, id).await?;
+    let clear_after = config.clear_after_for(&roles, micros.mostly_hidden());
+    let now = Utc::now().timestamp() as u64;
+    let expired = match get_timestamp(omegga, format!("ts:{}", id)).await? {
+        Some(ts) => {
+            let clear_at = ts + (clear_after * 60.) as u64;
+            if now < clear_at {
+                let minutes = ((clear_at - now) as f32 / 60.).ceil();
+                omegga.whisper(
+                    player,
+                    messages.text("command-status-timer", &[("minutes", &minutes)]),
+                );
+            }
+            now >= clear_at
+        }
+        None => {
+            omegga.whisper(player, messages.get("command-status-no-timer"));
+            false
+        }
+    };
+
+    if !clear {
+        if expired {
+            omegga.whisper(
+                player,
+                messages.text("command-check-expired", &[("player", &target.name)]),
+            );
+        }
+        return Ok(());
+    }
+
+    if !expired {
+        omegga.whisper(player, messages.get("command-check-not-expired"));
+        return Ok(());
+    }
+
+    let summary = check_save(omegga, config, path.to_path_buf(), Some(id)).await?;
+    log(
+        omegga,
+        config,
+        LogLevel::Normal,
+        format!("{} checked and cleared {}", player, target.name),
+    );
+    if summary.cleared > 0 {
+        omegga.whisper(
+            player,
+            messages.text(
+                "command-check-cleared",
+                &[("player", &target.name), ("removed", &summary.removed)],
+            ),
+        );
+    } else {
+        omegga.whisper(
+            player,
+            messages.text("command-check-not-cleared", &[("player", &target.name)]),
+        );
+    }
+    Ok(())
+}
+
+/// `/am goto <player> [index]`: teleport to one of the clusters of microbricks
+/// a player was last flagged for.
+///
+/// With more than one cluster and no index, the clusters are listed instead.
+async fn goto(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, index) = split_trailing_count(args);
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-goto-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let sighting = match get_sighting(omegga, &target.id).await? {
+        Some(sighting) if !sighting.clusters.is_empty() => sighting,
+        _ => {
+            omegga.whisper(
+                player,
+                messages.text("command-goto-none", &[("player", &target.name)]),
+            );
+            return Ok(());
+        }
+    };
+
+    // positions from before the last scan may have moved or been removed since
+    let last_scan = get_stats(omegga).await?.last_scan.unwrap_or(0);
+    if sighting.time < last_scan {
+        let ago = ago_text(
+            messages,
+            (Utc::now().timestamp() as u64).saturating_sub(sighting.time),
+        );
+        omegga.whisper(
+            player.clone(),
+            messages.text("command-goto-stale", &[("ago", &ago)]),
+        );
+    }
+
+    let index = match index {
+        Some(index) => index,
+        None if sighting.clusters.len() == 1 => 1,
+        None => {
+            omegga.whisper(
+                player.clone(),
+                messages.text(
+                    "command-goto-list",
+                    &[
+                        ("player", &target.name),
+                        ("count", &sighting.clusters.len()),
+                    ],
+                ),
+            );
+            for (i, spot) in sighting.clusters.iter().enumerate() {
+                omegga.whisper(
+                    player.clone(),
+                    messages.text(
+                        "command-goto-row",
+                        &[
+                            ("index", &(i + 1)),
+                            ("count", &spot.count),
+                            ("x", &spot.x),
+                            ("y", &spot.y),
+                            ("z", &spot.z),
+                        ],
+                    ),
+                );
+            }
+            return Ok(());
+        }
+    };
+
+    let spot = match sighting.clusters.get(index.saturating_sub(1)) {
+        Some(spot) => spot,
+        None => {
+            omegga.whisper(
+                player,
+                messages.text(
+                    "command-goto-invalid",
+                    &[("count", &sighting.clusters.len())],
+                ),
+            );
+            return Ok(());
+        }
+    };
+
+    omegga.writeln(format!(
+        "Chat.Command /TP \"{}\" {} {} {} 0",
+        escape_command_arg(&player),
+        spot.x,
+        spot.y,
+        spot.z
+    ));
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-goto",
+            &[
+                ("player", &target.name),
+                ("count", &spot.count),
+                ("x", &spot.x),
+                ("y", &spot.y),
+                ("z", &spot.z),
+            ],
+        ),
+    );
+    Ok(())
+}
+
+/// `/am find`: save the world right now and report its microbricks by asset and owner
+/// in the background, without warning, timing, or clearing anyone.
+async fn find(omegga: &Arc<Omegga>, config: &Arc<RwLock<Config>>, player: String) -> Result<()> {
+    let messages = config.read().await.messages.clone();
+    if REPORTING.swap(true, Ordering::SeqCst) {
+        omegga.whisper(player, messages.get("command-scan-busy"));
+        return Ok(());
+    }
+
+    omegga.whisper(player.clone(), messages.get("command-find-started"));
+
+    let (omegga, config) = (Arc::clone(omegga), Arc::clone(config));
+    tokio::spawn(async move {
+        let result = find_now(&omegga, &config, &player).await;
+        REPORTING.store(false, Ordering::SeqCst);
+
+        if let Err(e) = result {
+            let messages = &config.read().await.messages;
+            omegga.error(format!("failed to run /am find: {}", e));
+            omegga.whisper(player, messages.text("command-failed", &[("error", &e)]));
+        }
+    });
+
+    Ok(())
+}
+
+/// Have the game write a fresh save, then report every microbrick in it.
+async fn find_now(omegga: &Omegga, config: &RwLock<Config>, player: &str) -> Result<()> {
+    let config = config.read().await.clone();
+    let path = save_now(omegga, &config, SCAN_SAVE_NAME).await?;
+
+    let result = find_in_save(omegga, &config, player, &path).await;
+    let _ = fs::remove_file(&path);
+    result
+}
+
+async fn find_in_save(omegga: &Omegga, config: &Config, player: &str, path: &Path) -> Result<()> {
+    let messages = &config.messages;
+    let SaveData {
+        header2, bricks, ..
+    } = read_save(path).await?;
+
+    // counted exactly the way a scan counts them, so this is what a scan would act on
+    let minigames = if config.skip_minigame_owners {
+        minigame_names(omegga).await
+    } else {
+        vec![]
+    };
+    let analysis = analyze_bricks(config, &header2, &bricks, None, &minigames);
+
+    // regroup each owner's counts by asset
+    let mut assets: HashMap<&str, Vec<(String, u32)>> = HashMap::new();
+    for micros in analysis.owners.values() {
+        for (&asset, &count) in micros.assets.iter() {
+            let owners = assets.entry(asset).or_default();
+            owners.push((micros.owner.name.clone(), count));
+        }
+    }
+    let public = messages.get("command-find-public");
+    for (&asset, &count) in analysis.public.iter() {
+        assets
+            .entry(asset)
+            .or_default()
+            .push((public.to_string(), count));
+    }
+
+    let in_minigames = analysis.minigames.values().sum::<u32>();
+    let total = assets
+        .values()
+        .flat_map(|owners| owners.iter().map(|(_, count)| count))
+        .sum::<u32>();
+    if total == 0 && in_minigames == 0 {
+        omegga.whisper(player, messages.get("command-find-clean"));
+        return Ok(());
+    }
+
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-find",
+            &[
+                ("count", &total),
+                ("assets", &assets.len()),
+                ("bricks", &bricks.len()),
+            ],
+        ),
+    );
+
+    let mut rows = assets
+        .into_iter()
+        .map(|(asset, mut owners)| {
+            owners.sort_by_key(|(name, count)| (std::cmp::Reverse(*count), name.to_lowercase()));
+            let count = owners.iter().map(|(_, count)| count).sum::<u32>();
+            (asset, count, owners)
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|&(asset, count, _)| (std::cmp::Reverse(count), asset));
+
+    for (asset, count, owners) in rows {
+        let owners = owners
+            .iter()
+            .map(|(name, count)| format!("{} x{}", name, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        omegga.whisper(
+            player,
+            messages.text(
+                "command-find-row",
+                &[("asset", &asset), ("count", &count), ("owners", &owners)],
+            ),
+        );
+    }
+
+    if in_minigames > 0 {
+        omegga.whisper(
+            player,
+            messages.text("command-find-minigames", &[("count", &in_minigames)]),
+        );
+    }
+    Ok(())
+}
+
+/// `/am simulate <save>`: report who has microbricks in a save from the Builds directory
+/// in the background, without warning, timing, clearing, or loading anything.
+async fn simulate(
+    omegga: &Arc<Omegga>,
+    config: &Arc<RwLock<Config>>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config_guard = config.read().await;
+    let messages = &config_guard.messages;
+    let name = args.join(" ");
+
+    let path = match contained_path(&config_guard.saves_dir, &name, "brs") {
+        Some(path) => path,
+        None => {
+            omegga.whisper(
+                player,
+                messages.text("command-simulate-invalid", &[("save", &name)]),
+            );
+            return Ok(());
+        }
+    };
+
+    if !path.is_file() {
+        omegga.whisper(
+            player,
+            messages.text("command-simulate-missing", &[("save", &name)]),
+        );
+        return Ok(());
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("command-simulate-started", &[("save", &name)]),
+    );
+    drop(config_guard);
+
+    let (omegga, config) = (Arc::clone(omegga), Arc::clone(config));
+    tokio::spawn(async move {
+        let config = config.read().await;
+        if let Err(e) = simulate_save(&omegga, &config, &player, &name, &path).await {
+            omegga.error(format!("failed to run /am simulate: {}", e));
+            omegga.whisper(
+                player,
+                config.messages.text("command-failed", &[("error", &e)]),
+            );
+        }
+    });
+
+    Ok(())
+}
+
+/// Resolve a file name to a path inside `dir`, adding `extension` if it's missing.
+///
+/// Names that are empty, absolute, or step outside the directory with `..` are rejected.
+fn contained_path(dir: &str, name: &str, extension: &str) -> Option<PathBuf> {
+    let name = Path::new(name);
+    if name.as_os_str().is_empty() || !name.components().all(|c| matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let mut path = Path::new(dir).join(name);
+    if path.extension().and_then(|e| e.to_str()) != Some(extension) {
+        path.set_extension(extension);
+    }
+
+    // symlinks could still lead elsewhere, so check where the path really ends up
+    if let (Ok(dir), Ok(real)) = (fs::canonicalize(dir), fs::canonicalize(&path)) {
+        if !real.starts_with(dir) {
+            return None;
+        }
+    }
+
+    Some(path)
+}
+
+/// Count each owner's microbricks in a save by asset, and whisper them to the player.
+async fn simulate_save(
+    omegga: &Omegga,
+    config: &Config,
+    player: &str,
+    name: &str,
+    path: &Path,
+) -> Result<()> {
+    let messages = &config.messages;
+    let mut reader = SaveReader::new(File::open(path)?)?;
+    let header1 = reader.read_header1()?;
+    let header2 = reader.read_header2()?;
+
+    // the same shortcut a scan takes: undersized bricks can be of any asset
+    if config.min_brick_size == 0
+        && !header2
+            .brick_assets
+            .iter()
+            .any(|asset| config.is_banned_asset(asset))
+    {
+        omegga.whisper(
+            player,
+            messages.text("command-simulate-clean", &[("save", &name)]),
+        );
+        return Ok(());
+    }
+
+    let SaveData {
+        header2, bricks, ..
+    } = read_rest(reader, header1, header2).await?;
+
+    // counted exactly the way a scan counts them
+    // (nobody is playing a minigame in a save on disk, so no owner is skipped for one)
+    let analysis = analyze_bricks(config, &header2, &bricks, None, &[]);
+    let mut owners = analysis
+        .owners
+        .values()
+        .map(|micros| (micros.owner.name.clone(), micros.assets.clone()))
+        .collect::<Vec<_>>();
+    if !analysis.public.is_empty() {
+        let public = messages.get("command-simulate-public").to_string();
+        owners.push((public, analysis.public));
+    }
+
+    let total = owners.iter().flat_map(|(_, a)| a.values()).sum::<u32>();
+    if total == 0 {
+        omegga.whisper(
+            player,
+            messages.text("command-simulate-clean", &[("save", &name)]),
+        );
+        return Ok(());
+    }
+
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-simulate",
+            &[
+                ("save", &name),
+                ("count", &total),
+                ("owners", &owners.len()),
+                ("bricks", &bricks.len()),
+            ],
+        ),
+    );
+
+    let mut rows = owners
+        .into_iter()
+        .map(|(name, assets)| {
+            let mut assets = assets.into_iter().collect::<Vec<_>>();
+            assets.sort_by_key(|&(asset, count)| (std::cmp::Reverse(count), asset));
+            (name, assets)
+        })
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(name, assets)| {
+        (
+            std::cmp::Reverse(assets.iter().map(|(_, count)| count).sum::<u32>()),
+            name.to_lowercase(),
+        )
+    });
+
+    for (name, assets) in rows {
+        let count = assets.iter().map(|(_, count)| count).sum::<u32>();
+        let assets = assets
+            .iter()
+            .map(|(asset, count)| format!("{} x{}", asset, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        omegga.whisper(
+            player,
+            messages.text(
+                "command-simulate-row",
+                &[("player", &name), ("count", &count), ("assets", &assets)],
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// `/am pardon <player> [count]`: take violations off a player's record, most recent first.
+async fn pardon(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+
+    let (query, count) = split_trailing_count(args);
+    let count = count.unwrap_or(1);
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-pardon-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let mut violations = get_violations(omegga, &target.id).await?;
+    let previous = violations.len();
+    violations.truncate(previous.saturating_sub(count));
+    set_violations(omegga, &target.id, &violations);
+
+    let removed = (previous - violations.len()) as u32;
+    log_action(
+        omegga,
+        Action::now(ActionKind::Pardon, &target.name, removed).by(&player),
+    )
+    .await?;
+
+    let total = active_violations(&violations, config.violation_expiry_days);
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-pardon",
+            &[("player", &target.name), ("violations", &total)],
+        ),
+    );
+
+    if let Some(p) = players.iter().find(|p| p.id == target.id) {
+        omegga.whisper(
+            p.name.clone(),
+            messages.text("command-pardon-target", &[("violations", &total)]),
+        );
+    }
+
+    Ok(())
+}
+
+/// `/am exempt add|remove|list`: manage exemptions kept in the store, alongside the config's.
+async fn exempt(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let query = args.get(1..).unwrap_or_default().join(" ");
+
+    match args.first().map(String::as_str) {
+        Some("add") => {
+            // only online players can be added, so there's no mistaking who is exempted
+            let matches = if query.is_empty() {
+                vec![]
+            } else {
+                match_online(players, &query)
+            };
+            let target = match matches.as_slice() {
+                [] => {
+                    omegga.whisper(player, messages.get("command-exempt-no-player"));
+                    return Ok(());
+                }
+                [p] => *p,
+                matches => {
+                    let names = matches
+                        .iter()
+                        .map(|p| p.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    omegga.whisper(
+                        player,
+                        messages.text("command-ambiguous-player", &[("players", &names)]),
+                    );
+                    return Ok(());
+                }
+            };
+
+            omegga.store_set(
+                format!("exempt:{}", target.id),
+                Value::String(target.name.clone()),
+            );
+            // whatever they were on the clock for, microbricks or a category
+            for timer in timer_ids(&config, &target.id) {
+                for prefix in TIMER_PREFIXES {
+                    omegga.store_delete(format!("{}:{}", prefix, timer)).await;
+                }
+            }
+            omegga.whisper(
+                player,
+                messages.text("command-exempt-add", &[("player", &target.name)]),
+            );
+        }
+        Some("remove") => {
+            let exempt = get_exempt(omegga).await?;
+            let target = exempt
+                .iter()
+                .find(|(id, name)| id.to_string() == query || name.eq_ignore_ascii_case(&query))
+                .map(|(id, name)| (*id, name.clone()));
+
+            match target {
+                Some((id, name)) => {
+                    omegga.store_delete(format!("exempt:{}", id)).await;
+                    omegga.whisper(
+                        player,
+                        messages.text("command-exempt-remove", &[("player", &name)]),
+                    );
+                }
+                None => omegga.whisper(
+                    player,
+                    messages.text("command-exempt-not-found", &[("player", &query)]),
+                ),
+            }
+        }
+        Some("list") => {
+            let exempt = get_exempt(omegga).await?;
+            if exempt.is_empty() {
+                omegga.whisper(player, messages.get("command-exempt-list-empty"));
+                return Ok(());
+            }
+
+            let mut names = exempt.into_values().collect::<Vec<_>>();
+            names.sort_by_key(|name| name.to_lowercase());
+            omegga.whisper(
+                player,
+                messages.text("command-exempt-list", &[("players", &names.join(", "))]),
+            );
+        }
+        _ => omegga.whisper(player, messages.get("command-exempt-usage")),
+    }
+
+    Ok(())
+}
+
+/// `/am permit <player> <duration>`: let a player use microbricks until the duration is up,
+/// after which scans enforce as usual again.
+async fn permit(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, seconds) = match args.split_last() {
+        Some((last, rest)) if !rest.is_empty() => (rest.join(" "), parse_duration(last)),
+        _ => (args.join(" "), None),
+    };
+
+    let seconds = match seconds {
+        Some(seconds) if seconds > 0 => seconds,
+        _ => {
+            omegga.whisper(player, messages.get("command-permit-usage"));
+            return Ok(());
+        }
+    };
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-permit-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let expires = Utc::now().timestamp() as u64 + seconds;
+    omegga.store_set(
+        format!("permit:{}", target.id),
+        Value::String(expires.to_string()),
+    );
+    omegga
+        .store_delete(format!("permitnotice:{}", target.id))
+        .await;
+
+    let duration = duration_text(messages, seconds as f32 / 60.);
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} permitted {} to use microbricks for {}",
+            player, target.name, duration
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-permit",
+            &[
+                ("player", &target.name),
+                ("duration", &duration),
+                ("time", &format_time(expires)),
+            ],
+        ),
+    );
+
+    if let Some(p) = players.iter().find(|p| p.id == target.id) {
+        omegga.whisper(
+            p.name.clone(),
+            messages.text("command-permit-target", &[("duration", &duration)]),
+        );
+    }
+    Ok(())
+}
+
+/// `/am watch <player>`: put a player under strict scrutiny until `/am unwatch`,
+/// so any microbrick of theirs is cleared on the next scan and counts as a violation.
+async fn watch(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &args.join(" "),
+        "command-watch-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    omegga.store_set(
+        format!("watch:{}", target.id),
+        Value::String(target.name.clone()),
+    );
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!("{} started watching {}", player, target.name),
+    );
+    omegga.whisper(
+        player,
+        messages.text("command-watch", &[("player", &target.name)]),
+    );
+    Ok(())
+}
+
+/// `/am unwatch <player>`: stop watching a player, so scans treat them like anyone else.
+async fn unwatch(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &args.join(" "),
+        "command-watch-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let key = format!("watch:{}", target.id);
+    if omegga.store_get(key.clone()).await?.is_none() {
+        omegga.whisper(
+            player,
+            messages.text("command-unwatch-not-watched", &[("player", &target.name)]),
+        );
+        return Ok(());
+    }
+
+    omegga.store_delete(key).await;
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!("{} stopped watching {}", player, target.name),
+    );
+    omegga.whisper(
+        player,
+        messages.text("command-unwatch", &[("player", &target.name)]),
+    );
+    Ok(())
+}
+
+/// Parse a duration like `2h`, `30m`, `1d`, or `90s` into seconds. A bare number is in minutes.
+fn parse_duration(arg: &str) -> Option<u64> {
+    let (number, unit) = match arg.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => arg.split_at(i),
+        None => (arg, "m"),
+    };
+
+    let scale = match unit.to_lowercase().as_str() {
+        "s" => 1.,
+        "m" => 60.,
+        "h" => 60. * 60.,
+        "d" => 60. * 60. * 24.,
+        _ => return None,
+    };
+
+    match number.parse::<f32>() {
+        Ok(n) if n.is_finite() && n >= 0. => Some((n * scale) as u64),
+        _ => None,
+    }
+}
+
+/// `/am allowasset add|remove|list [asset]`: manage the assets allowed at runtime,
+/// taking effect from the next scan.
+async fn allow_asset(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let mut config = config.write().await;
+    let asset = args.get(1..).unwrap_or_default().join(" ");
+
+    let message = match args.first().map(String::as_str) {
+        Some("add") if !asset.is_empty() => {
+            if !config.runtime_allowed_assets.contains(&asset) {
+                let mut assets = config.runtime_allowed_assets.clone();
+                assets.push(asset.clone());
+                config.set_runtime_allowed_assets(assets);
+                set_allowed_assets(omegga, &config.runtime_allowed_assets);
+                log(
+                    omegga,
+                    &config,
+                    LogLevel::Normal,
+                    format!("{} allowed assets matching {}", player, asset),
+                );
+            }
+            config
+                .messages
+                .text("command-allowasset-add", &[("asset", &asset)])
+        }
+        Some("remove") if !asset.is_empty() => {
+            let mut assets = config.runtime_allowed_assets.clone();
+            assets.retain(|a| *a != asset);
+            if assets.len() == config.runtime_allowed_assets.len() {
+                config
+                    .messages
+                    .text("command-allowasset-not-found", &[("asset", &asset)])
+            } else {
+                config.set_runtime_allowed_assets(assets);
+                set_allowed_assets(omegga, &config.runtime_allowed_assets);
+                log(
+                    omegga,
+                    &config,
+                    LogLevel::Normal,
+                    format!("{} stopped allowing assets matching {}", player, asset),
+                );
+                config
+                    .messages
+                    .text("command-allowasset-remove", &[("asset", &asset)])
+            }
+        }
+        Some("list") => {
+            let messages = &config.messages;
+            let list = |assets: &[String]| match assets {
+                [] => messages.get("command-allowasset-none").to_string(),
+                assets => assets.join(", "),
+            };
+            omegga.whisper(
+                player.clone(),
+                messages.text(
+                    "command-allowasset-list",
+                    &[("assets", &list(&config.runtime_allowed_assets))],
+                ),
+            );
+            messages.text(
+                "command-allowasset-list-config",
+                &[("assets", &list(&config.allowed_assets))],
+            )
+        }
+        _ => config.messages.get("command-allowasset-usage").to_string(),
+    };
+
+    omegga.whisper(player, message);
+    Ok(())
+}
+
+/// `/am threshold <name> <value>`, `/am threshold reset <name>`, and `/am threshold list`:
+/// override detection settings at runtime, kept across restarts in the store.
+async fn threshold(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let mut config = config.write().await;
+    let names = THRESHOLDS.join(", ");
+
+    let message = match (args.first().map(String::as_str), args.get(1)) {
+        (Some("list"), None) => {
+            if config.runtime_thresholds.is_empty() {
+                omegga.whisper(player, config.messages.get("command-threshold-list-empty"));
+                return Ok(());
+            }
+
+            for (name, value) in config.runtime_thresholds.iter() {
+                omegga.whisper(
+                    player.clone(),
+                    config.messages.text(
+                        "command-threshold-row",
+                        &[
+                            ("name", name),
+                            ("value", value),
+                            ("file", &config.file_thresholds[name]),
+                        ],
+                    ),
+                );
+            }
+            return Ok(());
+        }
+        (Some("reset"), Some(name)) => match config.reset_threshold(name) {
+            Some(value) => {
+                set_thresholds(omegga, &config.runtime_thresholds);
+                log(
+                    omegga,
+                    &config,
+                    LogLevel::Normal,
+                    format!("{} reset {} to {}", player, name, value),
+                );
+                config.messages.text(
+                    "command-threshold-reset",
+                    &[("name", name), ("value", &value)],
+                )
+            }
+            None => config
+                .messages
+                .text("command-threshold-not-overridden", &[("name", name)]),
+        },
+        (Some(name), Some(value)) if config.threshold(name).is_some() => {
+            let value = match value.parse::<f64>() {
+                Ok(value) => config.set_threshold(name, value).map(|_| value),
+                Err(_) => Err(format!("{} isn't a number", value)),
+            };
+            match value {
+                Ok(value) => {
+                    set_thresholds(omegga, &config.runtime_thresholds);
+                    log(
+                        omegga,
+                        &config,
+                        LogLevel::Normal,
+                        format!("{} set {} to {}", player, name, value),
+                    );
+                    config.messages.text(
+                        "command-threshold-set",
+                        &[("name", &name), ("value", &value)],
+                    )
+                }
+                Err(e) => config
+                    .messages
+                    .text("command-threshold-invalid", &[("error", &e)]),
+            }
+        }
+        _ => config
+            .messages
+            .text("command-threshold-usage", &[("names", &names)]),
+    };
+
+    omegga.whisper(player, message);
+    Ok(())
+}
+
+/// `/am top [page]`: list the players with the most violations.
+async fn top(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let number = page_number(args.first());
+
+    let ids = omegga
+        .store_keys()
+        .await?
+        .iter()
+        .filter_map(|key| {
+            key.strip_prefix("violations:")
+                .or_else(|| key.strip_prefix("bans:"))
+                .map(String::from)
+        })
+        .collect::<BTreeSet<_>>();
+
+    if ids.is_empty() {
+        omegga.whisper(player, messages.get("command-top-empty"));
+        return Ok(());
+    }
+
+    let mut rows = vec![];
+    for id in ids.iter() {
+        let name = match players.iter().find(|p| &p.id == id) {
+            Some(p) => p.name.clone(),
+            None => get_name(omegga, id).await?.unwrap_or_else(|| id.clone()),
+        };
+        let violations = active_violations(
+            &get_violations(omegga, id).await?,
+            config.violation_expiry_days,
+        );
+        let bans = get_count(omegga, format!("bans:{}", id)).await?;
+        rows.push((name, violations, bans));
+    }
+
+    rows.sort_by_key(|(_, violations, bans)| std::cmp::Reverse((*violations, *bans)));
+
+    let page = match paginate(omegga, &config, &player, &rows, number) {
+        Some(page) => page,
+        None => return Ok(()),
+    };
+    for (i, (name, violations, bans)) in page.rows.iter().enumerate() {
+        omegga.whisper(
+            player.clone(),
+            messages.text(
+                "command-top-row",
+                &[
+                    ("rank", &(page.offset + i + 1)),
+                    ("player", name),
+                    ("violations", violations),
+                    ("bans", bans),
+                ],
+            ),
+        );
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("command-top-total", &[("count", &rows.len())]),
+    );
+    whisper_page_footer(omegga, messages, &player, &page, "top");
+    Ok(())
+}
+
+/// `/am history <player> [page]`: list a player's clears, most recent first.
+///
+/// Violations migrated from the old count-only records have no clear to go with them,
+/// so they're listed first as legacy violations.
+async fn history(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, number) = split_trailing_count(args);
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-history-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let legacy = get_violations(omegga, &target.id)
+        .await?
+        .iter()
+        .filter(|v| v.legacy)
+        .count();
+    let mut entries = vec![messages.get("command-history-legacy").to_string(); legacy];
+
+    let now = Utc::now().timestamp() as u64;
+    for clear in get_history(omegga, &target.id).await? {
+        let ago = ago_text(messages, now.saturating_sub(clear.time));
+        let mut entry = match clear.save {
+            Some(save) => messages.text(
+                "command-history-row",
+                &[("ago", &ago), ("removed", &clear.removed), ("save", &save)],
+            ),
+            None => messages.text(
+                "command-history-row-unsaved",
+                &[("ago", &ago), ("removed", &clear.removed)],
+            ),
+        };
+        if let Some(category) = &clear.category {
+            entry.push_str(&messages.text("command-history-category", &[("category", category)]));
+        }
+        if clear.hidden {
+            entry.push_str(messages.get("command-history-hidden"));
+        }
+        if clear.concealed {
+            entry.push_str(messages.get("command-history-concealed"));
+        }
+        entries.push(entry);
+    }
+
+    if entries.is_empty() {
+        omegga.whisper(
+            player,
+            messages.text("command-history-none", &[("player", &target.name)]),
+        );
+        return Ok(());
+    }
+
+    // the most recent entries come first, so the first page is what's new
+    entries.reverse();
+    let page = match paginate(omegga, &config, &player, &entries, number.unwrap_or(1)) {
+        Some(page) => page,
+        None => return Ok(()),
+    };
+
+    omegga.whisper(
+        player.clone(),
+        messages.text(
+            "command-history",
+            &[("player", &target.name), ("count", &entries.len())],
+        ),
+    );
+    for entry in page.rows.iter() {
+        omegga.whisper(player.clone(), entry.clone());
+    }
+    whisper_page_footer(
+        omegga,
+        messages,
+        &player,
+        &page,
+        &format!("history {}", target.name),
+    );
+    Ok(())
+}
+
+/// `/am stats [reset]`: show the enforcement totals, or reset them.
+async fn stats(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+
+    if args.first().map(String::as_str) == Some("reset") {
+        omegga.store_delete("stats").await;
+        log(
+            omegga,
+            &config,
+            LogLevel::Normal,
+            format!("{} reset the stats", player),
+        );
+        omegga.whisper(player, messages.get("command-stats-reset"));
+        return Ok(());
+    }
+
+    let stats = get_stats(omegga).await?;
+    omegga.whisper(
+        player.clone(),
+        messages.text(
+            "command-stats",
+            &[
+                ("scans", &format_count(stats.scans)),
+                ("clears", &format_count(stats.clears)),
+                ("removed", &format_count(stats.removed)),
+                ("bans", &format_count(stats.bans)),
+            ],
+        ),
+    );
+
+    omegga.whisper(
+        player,
+        match stats.last_scan {
+            Some(time) => messages.text(
+                "command-stats-last-scan",
+                &[
+                    (
+                        "ago",
+                        &ago_text(
+                            messages,
+                            (Utc::now().timestamp() as u64).saturating_sub(time),
+                        ),
+                    ),
+                    ("bricks", &format_count(stats.last_scan_bricks)),
+                ],
+            ),
+            None => messages.get("command-stats-never").to_string(),
+        },
+    );
+    Ok(())
+}
+
+/// Describe how long ago something happened, in whole days, hours, or minutes.
+fn ago_text(messages: &Messages, seconds: u64) -> String {
+    let minutes = seconds / 60;
+    let minutes = if minutes >= 60 * 24 {
+        minutes / (60 * 24) * (60 * 24)
+    } else if minutes >= 60 {
+        minutes / 60 * 60
+    } else {
+        minutes
+    };
+    messages.text(
+        "command-history-ago",
+        &[("duration", &duration_text(messages, minutes as f32))],
+    )
+}
+
+/// `/am log [count]`: whisper the most recent enforcement actions, newest first.
+async fn show_log(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let count = match args.first() {
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(count) if count > 0 => count,
+            _ => {
+                omegga.whisper(
+                    player,
+                    messages.text("command-log-invalid", &[("count", arg)]),
+                );
+                return Ok(());
+            }
+        },
+        None => config.page_size as usize,
+    };
+
+    let actions = get_actions(omegga).await?;
+    if actions.is_empty() {
+        omegga.whisper(player, messages.get("command-log-empty"));
+        return Ok(());
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text(
+            "command-log",
+            &[
+                ("count", &count.min(actions.len())),
+                ("total", &actions.len()),
+            ],
+        ),
+    );
+
+    let now = Utc::now().timestamp() as u64;
+    for action in actions.iter().rev().take(count) {
+        let ago = ago_text(messages, now.saturating_sub(action.time));
+        omegga.whisper(
+            player.clone(),
+            messages.text(
+                &format!("command-log-{}", action.kind.name()),
+                &[
+                    ("ago", &ago),
+                    ("player", &action.player),
+                    ("count", &action.count),
+                    ("by", &action.by.as_deref().unwrap_or_default()),
+                ],
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// `/am pause` and `/am resume`: stop or start enforcement.
+async fn pause(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    paused: bool,
+) -> Result<()> {
+    let config = config.read().await;
+    set_paused(omegga, paused).await;
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} {} enforcement",
+            player,
+            if paused { "paused" } else { "resumed" }
+        ),
+    );
+    omegga.whisper(
+        player,
+        config.messages.get(if paused {
+            "command-pause"
+        } else {
+            "command-resume"
+        }),
+    );
+    Ok(())
+}
+
+/// `/am dryrun [on|off]`: turn dry-run mode on or off, or show whether it's on.
+async fn dry_run(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let on = match args.first().map(String::as_str) {
+        Some("on") => true,
+        Some("off") => false,
+        Some(_) => {
+            omegga.whisper(player, messages.get("command-dryrun-usage"));
+            return Ok(());
+        }
+        None => {
+            let key = if is_dry_run(omegga).await? {
+                "command-dryrun-on"
+            } else {
+                "command-dryrun-off"
+            };
+            omegga.whisper(player, messages.get(key));
+            return Ok(());
+        }
+    };
+
+    set_dry_run(omegga, on).await;
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} turned dry-run mode {}",
+            player,
+            if on { "on" } else { "off" }
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.get(if on {
+            "command-dryrun-on"
+        } else {
+            "command-dryrun-off"
+        }),
+    );
+    Ok(())
+}
+
+/// `/am notify [on|off]`: turn the invoking admin's notifications on or off,
+/// or show whether they're on.
+///
+/// The preference is kept by id, so it survives restarts and renames.
+async fn notify(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let id = match players.iter().find(|p| p.name == player) {
+        Some(p) => p.id.clone(),
+        None => return Ok(()),
+    };
+
+    let on = match args.first().map(String::as_str) {
+        Some("on") => true,
+        Some("off") => false,
+        Some(_) => {
+            omegga.whisper(player, messages.get("command-notify-usage"));
+            return Ok(());
+        }
+        None => {
+            let key = if wants_notifications(omegga, &id).await? {
+                "command-notify-on"
+            } else {
+                "command-notify-off"
+            };
+            omegga.whisper(player, messages.get(key));
+            return Ok(());
+        }
+    };
+
+    set_notifications(omegga, &id, on).await;
+    omegga.whisper(
+        player,
+        messages.get(if on {
+            "command-notify-on"
+        } else {
+            "command-notify-off"
+        }),
+    );
+    Ok(())
+}
+
+/// `/am setviolations <player> <count>` and `/am setbans <player> <count>`:
+/// set a player's violations or bans directly.
+///
+/// Violations are trimmed from the oldest, or added as new violations timestamped now.
+async fn set_count(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+    bans: bool,
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, count) = split_trailing_count(args);
+
+    let count = match count {
+        Some(count) if count <= MAX_SET_COUNT => count,
+        _ => {
+            omegga.whisper(
+                player,
+                messages.text("command-set-invalid", &[("max", &MAX_SET_COUNT)]),
+            );
+            return Ok(());
+        }
+    };
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-set-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let (record, previous) = if bans {
+        let key = format!("bans:{}", target.id);
+        let previous = get_count(omegga, key.clone()).await? as usize;
+        omegga.store_set(key, count.into());
+        ("bans", previous)
+    } else {
+        let mut violations = get_violations(omegga, &target.id).await?;
+        let previous = violations.len();
+        violations.drain(..previous.saturating_sub(count));
+        violations.extend((previous..count).map(|_| Violation::now()));
+        set_violations(omegga, &target.id, &violations);
+        ("violations", previous)
+    };
+
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} set the {} of {} from {} to {}",
+            player, record, target.name, previous, count
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            if bans {
+                "command-set-bans"
+            } else {
+                "command-set-violations"
+            },
+            &[
+                ("player", &target.name),
+                ("previous", &previous),
+                ("count", &count),
+            ],
+        ),
+    );
+    Ok(())
+}
+
+/// `/am unban <player> [reset]`: lift a player's ban, then take one ban off their count,
+/// or reset it entirely so their next ban starts from the first duration again.
+async fn unban(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, reset) = match args.split_last() {
+        Some((last, rest)) if last == "reset" && !rest.is_empty() => (rest.join(" "), true),
+        _ => (args.join(" "), false),
+    };
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-unban-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    omegga.writeln(format!("Chat.Command /Unban {}", target.id));
+
+    let key = format!("bans:{}", target.id);
+    let previous = get_count(omegga, key.clone()).await?;
+    let bans = if reset { 0 } else { (previous - 1).max(0) };
+    if bans == 0 {
+        omegga.store_delete(key).await;
+    } else {
+        omegga.store_set(key, bans.into());
+    }
+
+    log_action(
+        omegga,
+        Action::now(ActionKind::Unban, &target.name, bans as u32).by(&player),
+    )
+    .await?;
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} unbanned {} (bans {} -> {})",
+            player, target.name, previous, bans
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-unban",
+            &[
+                ("player", &target.name),
+                ("previous", &previous),
+                ("bans", &bans),
+            ],
+        ),
+    );
+    Ok(())
+}
+
+/// `/am clearbans <player>`: forget how many times a player has been banned,
+/// leaving their violations and timer on record.
+async fn clear_bans(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &args.join(" "),
+        "command-clearbans-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let key = format!("bans:{}", target.id);
+    let previous = get_count(omegga, key.clone()).await?;
+    if previous == 0 {
+        omegga.whisper(
+            player,
+            messages.text("command-clearbans-none", &[("player", &target.name)]),
+        );
+        return Ok(());
+    }
+
+    omegga.store_delete(key).await;
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!(
+            "{} cleared the bans of {} ({} bans)",
+            player, target.name, previous
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-clearbans",
+            &[("player", &target.name), ("previous", &previous)],
+        ),
+    );
+    Ok(())
+}
+
+/// `/am restore <player> [index]`: load one of a player's quarantined snapshots back in,
+/// taking back the violation it came with.
+async fn restore(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+    let (query, index) = split_trailing_count(args);
+
+    let target = match resolve_target(
+        omegga,
+        messages,
+        players,
+        &player,
+        &query,
+        "command-restore-no-player",
+    )
+    .await?
+    {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+
+    let snapshots = quarantine::list(&config, &target.id)?;
+    let snapshot = match snapshots.get(index.unwrap_or(1).saturating_sub(1)) {
+        Some(snapshot) => snapshot,
+        None => {
+            omegga.whisper(
+                player,
+                messages.text(
+                    "command-restore-none",
+                    &[("player", &target.name), ("count", &snapshots.len())],
+                ),
+            );
+            return Ok(());
+        }
+    };
+
+    omegga
+        .load_bricks(
+            snapshot.name.as_str(),
+            config.reload_quiet,
+            config.reload_offset(),
+        )
+        .await?;
+
+    // only the violation that quarantined these bricks is taken back
+    let mut violations = get_violations(omegga, &target.id).await?;
+    if let Some(i) = violations
+        .iter()
+        .position(|v| v.save.as_deref() == Some(snapshot.name.as_str()))
+    {
+        violations.remove(i);
+        set_violations(omegga, &target.id, &violations);
+    }
+
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!("{} restored {}", player, snapshot.name),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-restore",
+            &[
+                ("player", &target.name),
+                ("time", &format_time(snapshot.time)),
+                (
+                    "violations",
+                    &active_violations(&violations, config.violation_expiry_days),
+                ),
+            ],
+        ),
+    );
+    Ok(())
+}
+
+/// `/am undo`: load the bricks removed by the most recent clear back in,
+/// taking back the violations it added.
+///
+/// Each undo moves on to the clear before it. Clears whose bricks weren't kept
+/// (or have since been purged) can't be undone, and are skipped over by the next undo.
+async fn undo(omegga: &Omegga, config: &RwLock<Config>, player: String) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+
+    let mut clears = get_recent_clears(omegga).await?;
+    let clear = match clears.pop() {
+        Some(clear) => clear,
+        None => {
+            omegga.whisper(player, messages.get("command-undo-none"));
+            return Ok(());
+        }
+    };
+    set_recent_clears(omegga, &clears);
+
+    let time = format_time(clear.time);
+    let save = clear.save.as_ref().filter(|save| {
+        Path::new(&config.saves_dir)
+            .join(format!("{}.brs", save))
+            .is_file()
+    });
+    let save = match save {
+        Some(save) => save,
+        None => {
+            omegga.whisper(
+                player,
+                messages.text(
+                    "command-undo-missing",
+                    &[("player", &clear.name), ("time", &time)],
+                ),
+            );
+            return Ok(());
+        }
+    };
+
+    omegga
+        .load_bricks(save.as_str(), config.reload_quiet, config.reload_offset())
+        .await?;
+
+    let mut violations = get_violations(omegga, &clear.owner).await?;
+    violations.truncate(violations.len().saturating_sub(clear.added as usize));
+    set_violations(omegga, &clear.owner, &violations);
+
+    log_action(
+        omegga,
+        Action::now(ActionKind::Undo, &clear.name, clear.added).by(&player),
+    )
+    .await?;
+    log(
+        omegga,
+        &config,
+        LogLevel::Normal,
+        format!("{} undid the clear of {} ({})", player, clear.name, save),
+    );
+    omegga.whisper(
+        player.clone(),
+        messages.text(
+            "command-undo",
+            &[
+                ("player", &clear.name),
+                ("time", &time),
+                (
+                    "violations",
+                    &active_violations(&violations, config.violation_expiry_days),
+                ),
+            ],
+        ),
+    );
+
+    // a ban isn't lifted without asking, since it may have been deserved anyway
+    if clear.banned {
+        omegga.whisper(
+            player,
+            messages.text(
+                "command-undo-banned",
+                &[("player", &clear.name), ("id", &clear.owner)],
+            ),
+        );
+    }
+    Ok(())
+}
+
+/// `/am quarantine list [player] [page]`: list the snapshots of removed microbricks, grouped by owner
+/// and numbered the way `/am restore` takes them.
+///
+/// `/am quarantine purge <days>` deletes snapshots older than `days` instead.
+async fn show_quarantine(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    players: &[Player],
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let messages = &config.messages;
+
+    // a lone number is a page, since there's no player to tell it apart from
+    let (query, number) = match args.get(1..).unwrap_or_default() {
+        [arg] if arg.parse::<usize>().is_ok() => (String::new(), page_number(Some(arg))),
+        rest => {
+            let (query, number) = split_trailing_count(rest);
+            (query, number.unwrap_or(1))
+        }
+    };
+
+    match args.first().map(String::as_str) {
+        Some("list") => {}
+        Some("purge") => return purge_quarantine(omegga, &config, player, &args[1..]),
+        _ => {
+            omegga.whisper(player, messages.get("command-quarantine-usage"));
+            return Ok(());
+        }
+    }
+
+    // with a player, only their snapshots are listed
+    let snapshots = if query.is_empty() {
+        quarantine::list_all(&config)?
+    } else {
+        match resolve_target(
+            omegga,
+            messages,
+            players,
+            &player,
+            &query,
+            "command-quarantine-no-player",
+        )
+        .await?
+        {
+            Some(target) => quarantine::list(&config, &target.id)?,
+            None => return Ok(()),
+        }
+    };
+
+    if snapshots.is_empty() {
+        omegga.whisper(player, messages.get("command-quarantine-empty"));
+        return Ok(());
+    }
+
+    // owners are listed by their most recent snapshot, since snapshots come newest first
+    let mut owners: Vec<(&str, Vec<&quarantine::Snapshot>)> = vec![];
+    for snapshot in snapshots.iter() {
+        match owners.iter_mut().find(|(id, _)| *id == snapshot.owner) {
+            Some((_, group)) => group.push(snapshot),
+            None => owners.push((&snapshot.owner, vec![snapshot])),
+        }
+    }
+
+    // pages are split by snapshot, numbered the way restore takes them,
+    // so each owner's header is repeated on every page their snapshots are on
+    let rows = owners
+        .iter()
+        .flat_map(|(id, group)| {
+            group
+                .iter()
+                .enumerate()
+                .map(move |(i, snapshot)| (*id, group.len(), i + 1, *snapshot))
+        })
+        .collect::<Vec<_>>();
+    let page = match paginate(omegga, &config, &player, &rows, number) {
+        Some(page) => page,
+        None => return Ok(()),
+    };
+
+    let mut last_owner = None;
+    for &(id, count, index, snapshot) in page.rows.iter() {
+        if last_owner != Some(id) {
+            last_owner = Some(id);
+            let name = match players.iter().find(|p| p.id == id) {
+                Some(p) => p.name.clone(),
+                None => get_name(omegga, id)
+                    .await?
+                    .unwrap_or_else(|| id.to_string()),
+            };
+            omegga.whisper(
+                player.clone(),
+                messages.text(
+                    "command-quarantine-owner",
+                    &[("player", &name), ("count", &count)],
+                ),
+            );
+        }
+
+        let bricks = match quarantine::brick_count(&config, snapshot) {
+            Ok(bricks) => bricks.to_string(),
+            Err(_) => "?".into(),
+        };
+        omegga.whisper(
+            player.clone(),
+            messages.text(
+                "command-quarantine-row",
+                &[
+                    ("index", &index),
+                    ("time", &format_time(snapshot.time)),
+                    ("bricks", &bricks),
+                ],
+            ),
+        );
+    }
+
+    omegga.whisper(
+        player.clone(),
+        messages.text("command-quarantine-total", &[("count", &snapshots.len())]),
+    );
+    let command = format!("quarantine list {}", query);
+    whisper_page_footer(omegga, messages, &player, &page, command.trim_end());
+    Ok(())
+}
+
+/// `/am quarantine purge <days>`: delete snapshots older than `days`.
+///
+/// There's no default age, so nothing is deleted by accident.
+fn purge_quarantine(
+    omegga: &Omegga,
+    config: &Config,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let messages = &config.messages;
+    let days = match args.first().map(|arg| (arg, arg.parse::<f32>())) {
+        Some((_, Ok(days))) if days > 0. => days,
+        Some((arg, _)) => {
+            omegga.whisper(
+                player,
+                messages.text("command-clean-expired-invalid", &[("days", arg)]),
+            );
+            return Ok(());
+        }
+        None => {
+            omegga.whisper(player, messages.get("command-quarantine-usage"));
+            return Ok(());
+        }
+    };
+
+    let (count, bytes) = quarantine::purge(config, days)?;
+    let megabytes = format!("{:.1}", bytes as f64 / 1024. / 1024.);
+    log(
+        omegga,
+        config,
+        LogLevel::Normal,
+        format!(
+            "{} purged {} quarantined saves older than {} days ({} MB)",
+            player, count, days, megabytes
+        ),
+    );
+    omegga.whisper(
+        player,
+        messages.text(
+            "command-quarantine-purge",
+            &[
+                ("count", &count),
+                ("days", &days),
+                ("megabytes", &megabytes),
+            ],
+        ),
+    );
+    Ok(())
+}
+
+/// Split a trailing number off of a subcommand's arguments,
+/// returning the rest joined as the player and the number, if any.
+fn split_trailing_count(args: &[String]) -> (String, Option<usize>) {
+    match args.split_last() {
+        Some((last, rest)) if !rest.is_empty() => match last.parse() {
+            Ok(count) => (rest.join(" "), Some(count)),
+            Err(_) => (args.join(" "), None),
+        },
+        _ => (args.join(" "), None),
+    }
+}
+
+/// One page of a list-style subcommand's rows.
+struct Page<'a, T> {
+    /// The rows on this page.
+    rows: &'a [T],
+
+    /// How many rows come before this page.
+    offset: usize,
+
+    /// This page's number, starting from 1.
+    number: usize,
+
+    /// How many pages there are.
+    pages: usize,
+}
+
+/// Parse an optional page number argument, defaulting to the first page.
+///
+/// Anything that isn't a number is page 0, which never exists.
+fn page_number(arg: Option<&String>) -> usize {
+    arg.map_or(1, |arg| arg.parse().unwrap_or(0))
+}
+
+/// Pick one page of `rows`, split into pages of `page-size`,
+/// or whisper that there's no such page.
+fn paginate<'a, T>(
+    omegga: &Omegga,
+    config: &Config,
+    player: &str,
+    rows: &'a [T],
+    number: usize,
+) -> Option<Page<'a, T>> {
+    let size = config.page_size as usize;
+    let pages = rows.len().div_ceil(size).max(1);
+    if number == 0 || number > pages {
+        omegga.whisper(
+            player,
+            config
+                .messages
+                .text("command-page-invalid", &[("pages", &pages)]),
+        );
+        return None;
+    }
+
+    let offset = (number - 1) * size;
+    Some(Page {
+        rows: &rows[offset..(offset + size).min(rows.len())],
+        offset,
+        number,
+        pages,
+    })
+}
+
+/// Whisper which page was shown, and how to see the next one with `/am <command> <page>`.
+///
+/// Nothing is whispered when everything fits on one page.
+fn whisper_page_footer<T>(
+    omegga: &Omegga,
+    messages: &Messages,
+    player: &str,
+    page: &Page<T>,
+    command: &str,
+) {
+    if page.pages <= 1 {
+        return;
+    }
+
+    let footer = if page.number < page.pages {
+        messages.text(
+            "command-page-more",
+            &[
+                ("page", &page.number),
+                ("pages", &page.pages),
+                ("command", &command),
+                ("next", &(page.number + 1)),
+            ],
+        )
+    } else {
+        messages.text(
+            "command-page-last",
+            &[("page", &page.number), ("pages", &page.pages)],
+        )
+    };
+    omegga.whisper(player, footer);
+}
+
+/// Format a unix timestamp for admins to read.
+fn format_time(time: u64) -> String {
+    Utc.timestamp(time as i64, 0)
+        .format("%Y-%m-%d %H:%M UTC")
+        .to_string()
+}
+
+/// `/am config [full]`: show the active config, one setting per line.
+///
+/// Player lists are summarized as counts and message templates are left out, unless `full` is passed.
+async fn show_config(
+    omegga: &Omegga,
+    config: &RwLock<Config>,
+    player: String,
+    args: &[String],
+) -> Result<()> {
+    let config = config.read().await;
+    let full = args.first().map(String::as_str) == Some("full");
+    let messages = &config.messages;
+
+    let values = match serde_json::to_value(&*config)? {
+        Value::Object(values) => values,
+        _ => return Ok(()),
+    };
+
+    let templates = serde_json::to_value(messages)?;
+    for (key, value) in values.iter() {
+        let is_template = templates.get(key).is_some();
+        if is_template && !full {
+            continue;
+        }
+
+        let value = match value {
+            Value::Array(entries) if !full && SUMMARIZED_CONFIG_KEYS.contains(&key.as_str()) => {
+                messages.text("command-config-count", &[("count", &entries.len())])
+            }
+            value => value.to_string(),
+        };
+        let value = match config.file_thresholds.get(key) {
+            Some(file) => messages.text(
+                "command-config-override",
+                &[("value", &value), ("file", file)],
+            ),
+            None => value,
+        };
+        omegga.whisper(
+            player.clone(),
+            messages.text("command-config-row", &[("key", key), ("value", &value)]),
+        );
+    }
+
+    if !full {
+        omegga.whisper(player, messages.get("command-config-full"));
+    }
+    Ok(())
+}
@@ -0,0 +1,1349 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, File},
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::Result;
+use brickadia::save::{Brick, BrickOwner, Size};
+use omegga::resources::Player;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::messages::Messages;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthPlayer {
+    pub name: String,
+    pub id: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub authorized: Vec<AuthPlayer>,
+
+    #[serde(rename = "clear-after-minutes")]
+    pub clear_after: f32,
+
+    #[serde(rename = "confirmation-scans", default = "default_confirmation_scans")]
+    pub confirmation_scans: u32,
+
+    #[serde(rename = "clear-cooldown-minutes", default)]
+    pub clear_cooldown: f32,
+
+    #[serde(rename = "clear-after-overrides", default)]
+    pub clear_after_overrides: Vec<String>,
+
+    #[serde(skip)]
+    pub parsed_clear_after_overrides: Vec<(String, f32)>,
+
+    #[serde(rename = "new-player-grace-minutes", default)]
+    pub new_player_grace: f32,
+
+    #[serde(rename = "max-violations")]
+    pub max_violations: u32,
+
+    #[serde(rename = "violation-mode", default = "default_violation_mode")]
+    pub violation_mode: ViolationMode,
+
+    #[serde(
+        rename = "violation-brick-threshold",
+        default = "default_violation_brick_threshold"
+    )]
+    pub violation_brick_threshold: u32,
+
+    #[serde(rename = "violation-expiry-days", default)]
+    pub violation_expiry_days: f32,
+
+    #[serde(rename = "ban-time", default = "default_ban_time")]
+    pub ban_time: f32,
+
+    #[serde(rename = "ban-durations", default)]
+    pub ban_durations: Vec<f32>,
+
+    #[serde(rename = "enable-bans", default = "default_true")]
+    pub enable_bans: bool,
+
+    #[serde(rename = "kick-first", default)]
+    pub kick_first: bool,
+
+    #[serde(rename = "max-bans")]
+    pub max_bans: u32,
+
+    #[serde(rename = "autosave-plugins", default = "default_autosave_plugins")]
+    pub autosave_plugins: Vec<String>,
+
+    #[serde(rename = "summary-plugins", default)]
+    pub summary_plugins: Vec<String>,
+
+    #[serde(rename = "saves-dir", default = "default_saves_dir")]
+    pub saves_dir: String,
+
+    #[serde(rename = "temp-save-name", default = "default_temp_save_name")]
+    pub temp_save_name: String,
+
+    #[serde(rename = "reload-quiet", default = "default_true")]
+    pub reload_quiet: bool,
+
+    #[serde(rename = "reload-offset", default = "default_reload_offset")]
+    pub reload_offset: Vec<f64>,
+
+    #[serde(rename = "banned-assets", default = "default_banned_assets")]
+    pub banned_assets: Vec<String>,
+
+    #[serde(skip)]
+    pub parsed_banned_assets: Vec<AssetPattern>,
+
+    #[serde(rename = "allowed-assets", default)]
+    pub allowed_assets: Vec<String>,
+
+    /// Assets allowed at runtime with `/am allowasset`, kept in the store.
+    /// Set with `set_runtime_allowed_assets`.
+    #[serde(skip)]
+    pub runtime_allowed_assets: Vec<String>,
+
+    /// Every allowed asset, from the config and `/am allowasset`, normalized for matching.
+    #[serde(skip)]
+    pub parsed_allowed_assets: Vec<String>,
+
+    #[serde(rename = "convert-instead-of-clear", default)]
+    pub convert_instead_of_clear: bool,
+
+    #[serde(rename = "convert-asset", default = "default_convert_asset")]
+    pub convert_asset: String,
+
+    #[serde(rename = "allowed-regions", default)]
+    pub allowed_regions: Vec<String>,
+
+    #[serde(skip)]
+    pub parsed_allowed_regions: Vec<Region>,
+
+    #[serde(rename = "min-brick-size", default)]
+    pub min_brick_size: u32,
+
+    #[serde(rename = "min-cluster-size", default)]
+    pub min_cluster_size: u32,
+
+    #[serde(rename = "detect-concealed", default)]
+    pub detect_concealed: bool,
+
+    #[serde(default)]
+    pub rules: Vec<String>,
+
+    /// Each rule's pattern, normalized for matching, and severity.
+    #[serde(skip)]
+    pub parsed_rules: Vec<(String, Severity)>,
+
+    #[serde(default)]
+    pub categories: Vec<String>,
+
+    #[serde(skip)]
+    pub parsed_categories: Vec<Category>,
+
+    #[serde(rename = "ignore-save-patterns", default)]
+    pub ignore_save_patterns: Vec<String>,
+
+    #[serde(rename = "exempt-players", default)]
+    pub exempt_players: Vec<AuthPlayer>,
+
+    #[serde(rename = "ignore-owners", default)]
+    pub ignore_owners: Vec<String>,
+
+    #[serde(skip)]
+    pub parsed_ignore_owners: HashSet<Uuid>,
+
+    #[serde(rename = "exempt-roles", default)]
+    pub exempt_roles: Vec<String>,
+
+    #[serde(rename = "server-micro-alert", default)]
+    pub server_micro_alert: u32,
+
+    #[serde(rename = "public-micro-alert", default)]
+    pub public_micro_alert: u32,
+
+    #[serde(rename = "max-micro-count", default)]
+    pub max_micro_count: u32,
+
+    #[serde(rename = "max-micro-volume", default)]
+    pub max_micro_volume: u64,
+
+    #[serde(rename = "micro-volume-mode", default = "default_micro_volume_mode")]
+    pub micro_volume_mode: VolumeMode,
+
+    #[serde(rename = "micro-percent-threshold", default)]
+    pub micro_percent_threshold: f32,
+
+    #[serde(rename = "max-micro-density", default)]
+    pub max_micro_density: f32,
+
+    #[serde(rename = "announce-clear", default = "default_announce_clear")]
+    pub announce_clear: Channel,
+
+    #[serde(rename = "announce-warning", default = "default_announce_warning")]
+    pub announce_warning: Channel,
+
+    #[serde(rename = "announce-ban", default = "default_announce_ban")]
+    pub announce_ban: Channel,
+
+    #[serde(rename = "reply-unauthorized", default = "default_true")]
+    pub reply_unauthorized: bool,
+
+    #[serde(rename = "allow-wipe", default = "default_true")]
+    pub allow_wipe: bool,
+
+    #[serde(rename = "page-size", default = "default_page_size")]
+    pub page_size: u32,
+
+    #[serde(rename = "stale-record-days", default = "default_stale_record_days")]
+    pub stale_record_days: f32,
+
+    #[serde(rename = "quarantine-retention-days", default)]
+    pub quarantine_retention_days: f32,
+
+    #[serde(rename = "log-level", default = "default_log_level")]
+    pub log_level: LogLevel,
+
+    #[serde(rename = "skip-minigame-owners", default)]
+    pub skip_minigame_owners: bool,
+
+    #[serde(rename = "hidden-clear-after-minutes", default)]
+    pub hidden_clear_after: f32,
+
+    #[serde(rename = "escalate-on-growth", default)]
+    pub escalate_on_growth: bool,
+
+    #[serde(rename = "enforce-public", default)]
+    pub enforce_public: bool,
+
+    /// Settings overridden at runtime with `/am threshold`, kept in the store.
+    #[serde(skip)]
+    pub runtime_thresholds: BTreeMap<String, f64>,
+
+    /// The config file's values for each setting in `runtime_thresholds`.
+    #[serde(skip)]
+    pub file_thresholds: BTreeMap<String, f64>,
+
+    #[serde(flatten)]
+    pub messages: Messages,
+}
+
+/// The settings `/am threshold` can override at runtime.
+pub const THRESHOLDS: &[&str] = &[
+    "max-micro-count",
+    "min-brick-size",
+    "clear-after-minutes",
+    "new-player-grace-minutes",
+    "confirmation-scans",
+    "max-violations",
+    "violation-brick-threshold",
+    "server-micro-alert",
+];
+
+/// Normalize an asset name or pattern for matching: lowercased,
+/// without the `PB_` or `B_` prefix brick assets are usually named with.
+///
+/// This way `Micro` matches both `PB_DefaultMicroBrick` and a modded `B_microWedge_custom`.
+fn normalize_asset(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.strip_prefix("pb_").or_else(|| name.strip_prefix("b_")) {
+        Some(rest) => rest.to_string(),
+        None => name,
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_violation_mode() -> ViolationMode {
+    ViolationMode::PerClear
+}
+
+fn default_violation_brick_threshold() -> u32 {
+    100
+}
+
+fn default_confirmation_scans() -> u32 {
+    1
+}
+
+fn default_ban_time() -> f32 {
+    30.
+}
+
+fn default_page_size() -> u32 {
+    10
+}
+
+fn default_stale_record_days() -> f32 {
+    90.
+}
+
+fn default_log_level() -> LogLevel {
+    LogLevel::Normal
+}
+
+fn default_announce_clear() -> Channel {
+    Channel::Broadcast
+}
+
+fn default_announce_warning() -> Channel {
+    Channel::Whisper
+}
+
+fn default_announce_ban() -> Channel {
+    Channel::None
+}
+
+fn default_micro_volume_mode() -> VolumeMode {
+    VolumeMode::Either
+}
+
+fn default_autosave_plugins() -> Vec<String> {
+    vec!["autosave_ez".into()]
+}
+
+fn default_convert_asset() -> String {
+    "PB_DefaultBrick".into()
+}
+
+fn default_saves_dir() -> String {
+    "../../data/Saved/Builds".into()
+}
+
+fn default_temp_save_name() -> String {
+    "_anti_microbrick.brs".into()
+}
+
+fn default_reload_offset() -> Vec<f64> {
+    vec![0., 0., 0.]
+}
+
+fn default_banned_assets() -> Vec<String> {
+    vec!["Micro".into()]
+}
+
+/// Where an enforcement message is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    /// Sent to everyone on the server.
+    Broadcast,
+
+    /// Sent only to the player it concerns.
+    Whisper,
+
+    /// Not sent at all.
+    None,
+}
+
+/// How `max-micro-volume` combines with `max-micro-count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeMode {
+    /// Owners over either limit are enforced.
+    Either,
+
+    /// Owners are only enforced once they're over both limits.
+    Both,
+}
+
+/// How much the plugin logs to the console. Errors are always logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Only errors.
+    Quiet,
+
+    /// Clears, kicks, bans, and other enforcement.
+    Normal,
+
+    /// Everything, including each scan and the decision made for every owner.
+    Debug,
+}
+
+/// How violations are counted when a player's bricks are cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ViolationMode {
+    /// Every clear is one violation.
+    PerClear,
+
+    /// Every `violation-brick-threshold` bricks cleared is one violation.
+    PerBrickThreshold,
+}
+
+/// How harshly bricks of an asset are dealt with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The owner is warned, but their bricks are never cleared.
+    Warn,
+
+    /// The owner is warned, and their bricks are cleared once their timer expires.
+    Clear,
+
+    /// The owner's bricks are cleared on sight.
+    InstantClear,
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "warn" => Ok(Severity::Warn),
+            "clear" => Ok(Severity::Clear),
+            "instant-clear" => Ok(Severity::InstantClear),
+            _ => Err(format!(
+                "unknown severity \"{}\" (expected warn, clear, or instant-clear)",
+                s
+            )),
+        }
+    }
+}
+
+/// Parse a rule of the form `pattern=severity`.
+fn parse_rule(rule: &str) -> Result<(String, Severity), String> {
+    match rule.rsplit_once('=') {
+        Some((pattern, severity)) if !pattern.trim().is_empty() => {
+            Ok((pattern.trim().into(), severity.trim().parse()?))
+        }
+        _ => Err("expected an entry like \"MicroWedge=instant-clear\"".into()),
+    }
+}
+
+/// A banned asset pattern, compiled once when the config is loaded.
+#[derive(Clone)]
+pub enum AssetPattern {
+    /// A plain pattern, matched against normalized asset names. Stored normalized.
+    Substring(String),
+
+    /// A `re:` pattern, matched against the asset name as-is.
+    Regex(Regex),
+}
+
+impl AssetPattern {
+    /// Whether or not this pattern matches an asset, given both its raw and normalized names.
+    fn matches(&self, asset: &str, normalized: &str) -> bool {
+        match self {
+            AssetPattern::Substring(pattern) => normalized.contains(pattern.as_str()),
+            AssetPattern::Regex(regex) => regex.is_match(asset),
+        }
+    }
+}
+
+/// Parse a banned asset entry, either a plain substring or a `re:` regex.
+fn parse_asset_pattern(entry: &str) -> Result<AssetPattern, String> {
+    match entry.strip_prefix("re:") {
+        Some("") => Err("the regex is empty, which would match every brick".into()),
+        Some(regex) => Regex::new(regex)
+            .map(AssetPattern::Regex)
+            .map_err(|e| e.to_string()),
+        None => Ok(AssetPattern::Substring(normalize_asset(entry))),
+    }
+}
+
+/// How each of a save's brick assets is restricted, indexed by `asset_name_index`,
+/// so assets are matched once per save rather than once per brick.
+pub struct AssetRules {
+    severities: Vec<Option<Severity>>,
+    categories: Vec<Option<usize>>,
+}
+
+/// A named category of restricted assets besides microbricks, with its own budget and timers.
+#[derive(Clone)]
+pub struct Category {
+    pub name: String,
+
+    /// How many bricks of the category a player may place before they are warned.
+    pub max_count: u32,
+
+    patterns: Vec<AssetPattern>,
+}
+
+/// Parse a category of the form `name=pattern,pattern`, or `name:max=pattern,pattern`.
+fn parse_category(entry: &str) -> Result<Category, String> {
+    let (name, patterns) = match entry.split_once('=') {
+        Some((name, patterns)) if !patterns.trim().is_empty() => (name.trim(), patterns),
+        _ => return Err("expected an entry like \"lights:50=B_ModLight,B_1x1F_Round\"".into()),
+    };
+
+    let (name, max_count) = match name.split_once(':') {
+        Some((name, max)) => match max.trim().parse::<u32>() {
+            Ok(max) => (name.trim(), max),
+            Err(_) => return Err(format!("\"{}\" is not a whole number", max.trim())),
+        },
+        None => (name, 0),
+    };
+
+    // the name ends up in store keys and message keys
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "\"{}\" is not a valid name (use letters, numbers, - and _)",
+            name
+        ));
+    }
+
+    let patterns = patterns
+        .split(',')
+        .map(str::trim)
+        .map(|pattern| match pattern {
+            "" => Err("contains an empty pattern, which would match every brick".to_string()),
+            pattern => parse_asset_pattern(pattern),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Category {
+        name: name.into(),
+        max_count,
+        patterns,
+    })
+}
+
+/// An axis-aligned box of the world, in save units, with both corners inclusive.
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    min: (i32, i32, i32),
+    max: (i32, i32, i32),
+}
+
+impl Region {
+    /// Whether or not a position falls inside this region.
+    fn contains(&self, (x, y, z): (i32, i32, i32)) -> bool {
+        (self.min.0..=self.max.0).contains(&x)
+            && (self.min.1..=self.max.1).contains(&y)
+            && (self.min.2..=self.max.2).contains(&z)
+    }
+}
+
+/// Parse a region of the form `x1,y1,z1,x2,y2,z2`, with the corners in any order.
+fn parse_region(entry: &str) -> Result<Region, String> {
+    let coords = entry
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse::<i32>()
+                .map_err(|_| format!("\"{}\" is not a whole number", n.trim()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match coords[..] {
+        [x1, y1, z1, x2, y2, z2] => Ok(Region {
+            min: (x1.min(x2), y1.min(y2), z1.min(z2)),
+            max: (x1.max(x2), y1.max(y2), z1.max(z2)),
+        }),
+        _ => Err("expected an entry like \"-500,-500,0,500,500,300\"".into()),
+    }
+}
+
+/// Parse a clear-after override of the form `role=minutes`.
+fn parse_clear_after_override(entry: &str) -> Result<(String, f32), String> {
+    match entry.rsplit_once('=') {
+        Some((role, minutes)) if !role.trim().is_empty() => match minutes.trim().parse::<f32>() {
+            Ok(minutes) if minutes >= 0. => Ok((role.trim().into(), minutes)),
+            _ => Err(format!(
+                "\"{}\" is not a valid number of minutes",
+                minutes.trim()
+            )),
+        },
+        _ => Err("expected an entry like \"Trusted=30\"".into()),
+    }
+}
+
+/// A problem found while validating the config.
+#[derive(Debug, Clone)]
+pub enum Issue {
+    /// A problem the plugin can't safely enforce with.
+    Fatal(String),
+
+    /// A problem worth pointing out, but one the plugin can still run with.
+    Warning(String),
+}
+
+impl Issue {
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Issue::Fatal(_))
+    }
+}
+
+impl Config {
+    /// Read and deserialize the config from a file, along with any `messages.json` string overrides.
+    pub fn load(path: impl AsRef<Path>, messages_path: impl AsRef<Path>) -> Result<Self> {
+        let mut config: Config = serde_json::from_reader(File::open(path)?)?;
+        config.messages.load_overrides(messages_path)?;
+        config.messages.fill_defaults();
+        config.parse_entries();
+
+        // make sure the saves directory exists up front, rather than failing mid-scan.
+        // if this fails, validation will report it
+        let _ = fs::create_dir_all(&config.saves_dir);
+
+        Ok(config)
+    }
+
+    /// Fill in the parsed form of each list setting, skipping entries that don't parse
+    /// (`validate` reports those).
+    fn parse_entries(&mut self) {
+        self.parsed_banned_assets = self
+            .banned_assets
+            .iter()
+            .filter_map(|entry| parse_asset_pattern(entry).ok())
+            .collect();
+        self.parsed_categories = self
+            .categories
+            .iter()
+            .filter_map(|entry| parse_category(entry).ok())
+            .collect();
+        self.parsed_rules = self
+            .rules
+            .iter()
+            .filter_map(|rule| parse_rule(rule).ok())
+            .map(|(pattern, severity)| (normalize_asset(&pattern), severity))
+            .collect();
+        self.parsed_allowed_regions = self
+            .allowed_regions
+            .iter()
+            .filter_map(|entry| parse_region(entry).ok())
+            .collect();
+        self.parsed_ignore_owners = self
+            .ignore_owners
+            .iter()
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        self.parsed_clear_after_overrides = self
+            .clear_after_overrides
+            .iter()
+            .filter_map(|entry| parse_clear_after_override(entry).ok())
+            .collect();
+        let runtime = std::mem::take(&mut self.runtime_allowed_assets);
+        self.set_runtime_allowed_assets(runtime);
+    }
+
+    /// Replace the assets allowed with `/am allowasset`, normalizing every allowed asset again.
+    pub fn set_runtime_allowed_assets(&mut self, assets: Vec<String>) {
+        self.runtime_allowed_assets = assets;
+        self.parsed_allowed_assets = self
+            .allowed_assets
+            .iter()
+            .chain(self.runtime_allowed_assets.iter())
+            .map(|pattern| normalize_asset(pattern))
+            .collect();
+    }
+
+    /// Check the config for problems, returning every one found.
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = vec![];
+
+        if self.clear_after < 0. {
+            issues.push(Issue::Fatal(format!(
+                "clear-after-minutes must be at least 0 (got {})",
+                self.clear_after
+            )));
+        }
+
+        if self.convert_instead_of_clear {
+            if self.convert_asset.is_empty() {
+                issues.push(Issue::Fatal("convert-asset must not be empty".into()));
+            } else if self.is_banned_asset(&self.convert_asset) {
+                issues.push(Issue::Fatal(format!(
+                    "convert-asset {} is itself a banned asset",
+                    self.convert_asset
+                )));
+            }
+        }
+
+        if self.autosave_plugins.is_empty() {
+            issues.push(Issue::Warning(
+                "autosave-plugins is empty, no saves will be checked".into(),
+            ));
+        }
+
+        if self.confirmation_scans == 0 {
+            issues.push(Issue::Fatal("confirmation-scans must be at least 1".into()));
+        }
+
+        if self.clear_cooldown < 0. {
+            issues.push(Issue::Fatal(format!(
+                "clear-cooldown-minutes must be at least 0 (got {})",
+                self.clear_cooldown
+            )));
+        }
+
+        if self.new_player_grace < 0. {
+            issues.push(Issue::Fatal(format!(
+                "new-player-grace-minutes must be at least 0 (got {})",
+                self.new_player_grace
+            )));
+        }
+
+        if self.max_violations == 0 {
+            issues.push(Issue::Fatal(
+                "max-violations must be at least 1, otherwise every clear is a ban".into(),
+            ));
+        }
+
+        if self.violation_mode == ViolationMode::PerBrickThreshold
+            && self.violation_brick_threshold == 0
+        {
+            issues.push(Issue::Fatal(
+                "violation-brick-threshold must be at least 1".into(),
+            ));
+        }
+
+        if self.violation_expiry_days < 0. {
+            issues.push(Issue::Fatal(format!(
+                "violation-expiry-days must be at least 0 (got {})",
+                self.violation_expiry_days
+            )));
+        }
+
+        if self.hidden_clear_after < 0. {
+            issues.push(Issue::Fatal(format!(
+                "hidden-clear-after-minutes must be at least 0 (got {})",
+                self.hidden_clear_after
+            )));
+        }
+
+        if !(0. ..=100.).contains(&self.micro_percent_threshold) {
+            issues.push(Issue::Fatal(format!(
+                "micro-percent-threshold must be between 0 and 100 (got {})",
+                self.micro_percent_threshold
+            )));
+        }
+
+        if self.max_micro_density < 0. {
+            issues.push(Issue::Fatal(format!(
+                "max-micro-density must be at least 0 (got {})",
+                self.max_micro_density
+            )));
+        }
+
+        if self.page_size == 0 {
+            issues.push(Issue::Fatal("page-size must be greater than 0".into()));
+        }
+
+        if self.stale_record_days <= 0. {
+            issues.push(Issue::Fatal(format!(
+                "stale-record-days must be greater than 0 (got {})",
+                self.stale_record_days
+            )));
+        }
+
+        if self.quarantine_retention_days < 0. {
+            issues.push(Issue::Fatal(format!(
+                "quarantine-retention-days must be at least 0 (got {})",
+                self.quarantine_retention_days
+            )));
+        }
+
+        if self.ban_time < 0. {
+            issues.push(Issue::Fatal(format!(
+                "ban-time must be at least 0 (got {})",
+                self.ban_time
+            )));
+        }
+
+        if self.ban_durations.iter().any(|&d| d < 0.) {
+            issues.push(Issue::Fatal("ban-durations must all be at least 0".into()));
+        }
+
+        if !Path::new(&self.saves_dir).is_dir() {
+            issues.push(Issue::Fatal(format!(
+                "saves-dir \"{}\" does not exist and could not be created",
+                self.saves_dir
+            )));
+        }
+
+        if self.temp_save_name.is_empty() || self.temp_save_name.contains(['/', '\\']) {
+            issues.push(Issue::Fatal(format!(
+                "temp-save-name \"{}\" must be a plain file name",
+                self.temp_save_name
+            )));
+        }
+
+        if self.reload_offset.len() != 3 || self.reload_offset.iter().any(|n| n.fract() != 0.) {
+            issues.push(Issue::Fatal(format!(
+                "reload-offset must be 3 whole numbers (got {:?})",
+                self.reload_offset
+            )));
+        }
+
+        if self.authorized.is_empty() {
+            issues.push(Issue::Warning(
+                "no players are authorized, nobody will be able to use /am".into(),
+            ));
+        }
+
+        if self.banned_assets.is_empty() {
+            issues.push(Issue::Warning(
+                "banned-assets is empty, no bricks will be detected".into(),
+            ));
+        }
+
+        if self.banned_assets.iter().any(String::is_empty) {
+            issues.push(Issue::Fatal(
+                "banned-assets contains an empty pattern, which would match every brick".into(),
+            ));
+        }
+
+        for entry in self.banned_assets.iter().filter(|entry| !entry.is_empty()) {
+            if let Err(e) = parse_asset_pattern(entry) {
+                issues.push(Issue::Fatal(format!(
+                    "invalid banned asset \"{}\": {}",
+                    entry, e
+                )));
+            }
+        }
+
+        for rule in self.rules.iter() {
+            if let Err(e) = parse_rule(rule) {
+                issues.push(Issue::Fatal(format!("invalid rule \"{}\": {}", rule, e)));
+            }
+        }
+
+        let mut category_names = HashSet::new();
+        for entry in self.categories.iter() {
+            match parse_category(entry) {
+                Ok(category) if !category_names.insert(category.name.clone()) => {
+                    issues.push(Issue::Fatal(format!(
+                        "category \"{}\" is defined more than once",
+                        category.name
+                    )))
+                }
+                Ok(_) => {}
+                Err(e) => issues.push(Issue::Fatal(format!(
+                    "invalid category \"{}\": {}",
+                    entry, e
+                ))),
+            }
+        }
+
+        for entry in self.allowed_regions.iter() {
+            if let Err(e) = parse_region(entry) {
+                issues.push(Issue::Fatal(format!(
+                    "invalid allowed region \"{}\": {}",
+                    entry, e
+                )));
+            }
+        }
+
+        for entry in self.clear_after_overrides.iter() {
+            if let Err(e) = parse_clear_after_override(entry) {
+                issues.push(Issue::Fatal(format!(
+                    "invalid clear-after override \"{}\": {}",
+                    entry, e
+                )));
+            }
+        }
+
+        check_players(&mut issues, "authorized", &self.authorized);
+        check_players(&mut issues, "exempt-players", &self.exempt_players);
+
+        for id in self.ignore_owners.iter() {
+            if id.parse::<Uuid>().is_err() {
+                issues.push(Issue::Fatal(format!(
+                    "ignore-owners has an invalid id \"{}\"",
+                    id
+                )));
+            }
+        }
+
+        self.messages.validate(&mut issues);
+
+        issues
+    }
+
+    /// Describe each field that differs between this config and `other`, one line per field.
+    pub fn diff(&self, other: &Config) -> Vec<String> {
+        let (old, new) = match (serde_json::to_value(self), serde_json::to_value(other)) {
+            (Ok(Value::Object(old)), Ok(Value::Object(new))) => (old, new),
+            _ => return vec![],
+        };
+
+        new.iter()
+            .filter_map(|(key, value)| match old.get(key) {
+                Some(old_value) if old_value == value => None,
+                old_value => Some(format!(
+                    "<b>{}</>: {} -> {}",
+                    key,
+                    old_value.unwrap_or(&Value::Null),
+                    value
+                )),
+            })
+            .collect()
+    }
+
+    /// How many violations clearing `count` bricks is worth. Always at least 1.
+    pub fn violations_for(&self, count: u32) -> u32 {
+        match self.violation_mode {
+            ViolationMode::PerClear => 1,
+            ViolationMode::PerBrickThreshold => {
+                count.div_ceil(self.violation_brick_threshold.max(1)).max(1)
+            }
+        }
+    }
+
+    /// The length of a player's `bans`th temporary ban, in minutes.
+    ///
+    /// Indexes into `ban-durations` by ban count, clamping to the last entry, or uses
+    /// `ban-time` if no durations are configured.
+    pub fn ban_duration(&self, bans: u32) -> f32 {
+        match self.ban_durations.as_slice() {
+            [] => self.ban_time,
+            durations => durations[(bans.max(1) as usize - 1).min(durations.len() - 1)],
+        }
+    }
+
+    /// Whether or not a brick asset matches one of the banned asset patterns or rules.
+    pub fn is_banned_asset(&self, asset: &str) -> bool {
+        self.severity(asset).is_some()
+    }
+
+    /// How a brick should be dealt with, or `None` if it isn't restricted.
+    ///
+    /// On top of the asset checks, procedural bricks whose smallest dimension is under
+    /// `min-brick-size` are cleared as usual. Non-procedural bricks get their size from
+    /// their asset, so only the asset checks apply to them.
+    ///
+    /// Bricks inside one of `allowed-regions` are never restricted.
+    pub fn brick_severity(&self, rules: &AssetRules, brick: &Brick) -> Option<Severity> {
+        if self.in_allowed_region(brick) {
+            return None;
+        }
+
+        let undersized = match brick.size {
+            Size::Procedural(x, y, z) => x.min(y).min(z) < self.min_brick_size,
+            Size::Empty => false,
+        };
+
+        let severity = rules.severities[brick.asset_name_index as usize];
+        if undersized {
+            severity.max(Some(Severity::Clear))
+        } else {
+            severity
+        }
+    }
+
+    /// Whether or not a brick is inside one of `allowed-regions`.
+    fn in_allowed_region(&self, brick: &Brick) -> bool {
+        self.parsed_allowed_regions
+            .iter()
+            .any(|region| region.contains(brick.position))
+    }
+
+    /// The index of the first category a brick falls under, if any.
+    ///
+    /// Allowed assets and regions apply to categories the same way they do to microbricks.
+    pub fn brick_category(&self, rules: &AssetRules, brick: &Brick) -> Option<usize> {
+        if self.in_allowed_region(brick) {
+            return None;
+        }
+
+        rules.categories[brick.asset_name_index as usize]
+    }
+
+    /// Work out how each of a save's brick assets is restricted, for `brick_severity`
+    /// and `brick_category`.
+    pub fn asset_rules(&self, assets: &[String]) -> AssetRules {
+        AssetRules {
+            severities: assets.iter().map(|asset| self.severity(asset)).collect(),
+            categories: assets.iter().map(|asset| self.category(asset)).collect(),
+        }
+    }
+
+    /// The index of the first category an asset falls under, if any.
+    pub fn category(&self, asset: &str) -> Option<usize> {
+        let normalized = normalize_asset(asset);
+        if self.is_allowed_asset(&normalized) {
+            return None;
+        }
+
+        self.parsed_categories.iter().position(|category| {
+            category
+                .patterns
+                .iter()
+                .any(|pattern| pattern.matches(asset, &normalized))
+        })
+    }
+
+    /// A category's message, such as `category-warning-lights` from `messages.json`,
+    /// falling back to the shared `category-warning`.
+    pub fn category_message(&self, category: usize, kind: &str) -> &str {
+        let key = format!(
+            "category-{}-{}",
+            kind, self.parsed_categories[category].name
+        );
+        self.messages
+            .get_or(&key, self.messages.get(&format!("category-{}", kind)))
+    }
+
+    /// How bricks of an asset should be dealt with, or `None` if the asset isn't restricted.
+    ///
+    /// When several rules match, the most severe one wins. Banned assets without a matching
+    /// rule are cleared as usual. Assets and patterns are both normalized before matching.
+    pub fn severity(&self, asset: &str) -> Option<Severity> {
+        let normalized = normalize_asset(asset);
+        if self.is_allowed_asset(&normalized) {
+            return None;
+        }
+
+        let rule = self
+            .parsed_rules
+            .iter()
+            .filter(|(pattern, _)| normalized.contains(pattern.as_str()))
+            .map(|&(_, severity)| severity)
+            .max();
+
+        rule.or_else(|| {
+            self.parsed_banned_assets
+                .iter()
+                .any(|pattern| pattern.matches(asset, &normalized))
+                .then_some(Severity::Clear)
+        })
+    }
+
+    /// Whether or not an asset is allowed, in the config or with `/am allowasset`,
+    /// overriding any banned asset or rule it matches.
+    /// Expects an asset already passed through `normalize_asset`.
+    fn is_allowed_asset(&self, asset: &str) -> bool {
+        self.parsed_allowed_assets
+            .iter()
+            .any(|pattern| asset.contains(pattern.as_str()))
+    }
+
+    /// The offset to load the filtered save at.
+    pub fn reload_offset(&self) -> (i32, i32, i32) {
+        match self.reload_offset.as_slice() {
+            &[x, y, z] => (x as i32, y as i32, z as i32),
+            _ => (0, 0, 0),
+        }
+    }
+
+    /// Whether or not a save should be skipped entirely, given its path relative to the
+    /// builds directory.
+    ///
+    /// Patterns containing `*` or `?` are matched as globs against the whole path, anything
+    /// else is matched as a substring.
+    pub fn is_ignored_save(&self, path: &str) -> bool {
+        self.ignore_save_patterns.iter().any(|pattern| {
+            if pattern.contains(['*', '?']) {
+                glob_match(pattern.as_bytes(), path.as_bytes())
+            } else {
+                path.contains(pattern.as_str())
+            }
+        })
+    }
+
+    /// Whether or not a player is on the authorized list.
+    ///
+    /// Entries are matched by id, falling back to name only when the entry has no id.
+    pub fn is_authorized(&self, player: &Player) -> bool {
+        self.authorized.iter().any(|p| {
+            if p.id.is_empty() {
+                p.name.eq_ignore_ascii_case(&player.name)
+            } else {
+                p.id.eq_ignore_ascii_case(&player.id)
+            }
+        })
+    }
+
+    /// Whether or not any of a player's roles are exempt.
+    pub fn has_exempt_role(&self, roles: &[String]) -> bool {
+        roles.iter().any(|role| {
+            self.exempt_roles
+                .iter()
+                .any(|r| r.eq_ignore_ascii_case(role))
+        })
+    }
+
+    /// How many minutes a player with the given roles has before their bricks are cleared.
+    ///
+    /// Uses the most lenient matching override, or `clear-after-minutes` if none match,
+    /// cut down to `hidden-clear-after-minutes` if their bricks are mostly hidden.
+    pub fn clear_after_for(&self, roles: &[String], hidden: bool) -> f32 {
+        let minutes = self
+            .parsed_clear_after_overrides
+            .iter()
+            .filter(|(role, _)| roles.iter().any(|r| r.eq_ignore_ascii_case(role)))
+            .map(|&(_, minutes)| minutes)
+            .fold(None, |max: Option<f32>, minutes| {
+                Some(max.map_or(minutes, |max| max.max(minutes)))
+            })
+            .unwrap_or(self.clear_after);
+
+        if hidden {
+            minutes.min(self.hidden_clear_after)
+        } else {
+            minutes
+        }
+    }
+
+    /// Get the value of a setting `/am threshold` can override.
+    pub fn threshold(&self, name: &str) -> Option<f64> {
+        Some(match name {
+            "max-micro-count" => self.max_micro_count as f64,
+            "min-brick-size" => self.min_brick_size as f64,
+            "clear-after-minutes" => self.clear_after as f64,
+            "new-player-grace-minutes" => self.new_player_grace as f64,
+            "confirmation-scans" => self.confirmation_scans as f64,
+            "max-violations" => self.max_violations as f64,
+            "violation-brick-threshold" => self.violation_brick_threshold as f64,
+            "server-micro-alert" => self.server_micro_alert as f64,
+            _ => return None,
+        })
+    }
+
+    /// Override a setting at runtime, remembering its config file value so it can be reset.
+    ///
+    /// Counts must be whole numbers. If the new value leaves the config invalid, nothing changes.
+    pub fn set_threshold(&mut self, name: &str, value: f64) -> Result<(), String> {
+        let previous = self
+            .threshold(name)
+            .ok_or_else(|| format!("{} can't be overridden", name))?;
+        if !value.is_finite() || value < 0. {
+            return Err(format!("{} must be at least 0 (got {})", name, value));
+        }
+
+        // everything but the minute settings is a count
+        let minutes = matches!(name, "clear-after-minutes" | "new-player-grace-minutes");
+        if !minutes && (value.fract() != 0. || value > u32::MAX as f64) {
+            return Err(format!("{} must be a whole number (got {})", name, value));
+        }
+
+        self.write_threshold(name, value);
+        if let Some(Issue::Fatal(e)) = self.validate().into_iter().find(Issue::is_fatal) {
+            self.write_threshold(name, previous);
+            return Err(e);
+        }
+
+        self.file_thresholds.entry(name.into()).or_insert(previous);
+        self.runtime_thresholds.insert(name.into(), value);
+        Ok(())
+    }
+
+    /// Drop a runtime override, going back to the config file's value.
+    /// Returns that value, or `None` if the setting wasn't overridden.
+    pub fn reset_threshold(&mut self, name: &str) -> Option<f64> {
+        self.runtime_thresholds.remove(name)?;
+        let value = self.file_thresholds.remove(name)?;
+        self.write_threshold(name, value);
+        Some(value)
+    }
+
+    /// Apply runtime overrides, such as those kept in the store or from the config being replaced.
+    /// Overrides that are no longer valid are skipped and returned with why.
+    pub fn apply_thresholds(&mut self, thresholds: &BTreeMap<String, f64>) -> Vec<String> {
+        thresholds
+            .iter()
+            .filter_map(|(name, &value)| self.set_threshold(name, value).err())
+            .collect()
+    }
+
+    fn write_threshold(&mut self, name: &str, value: f64) {
+        match name {
+            "max-micro-count" => self.max_micro_count = value as u32,
+            "min-brick-size" => self.min_brick_size = value as u32,
+            "clear-after-minutes" => self.clear_after = value as f32,
+            "new-player-grace-minutes" => self.new_player_grace = value as f32,
+            "confirmation-scans" => self.confirmation_scans = value as u32,
+            "max-violations" => self.max_violations = value as u32,
+            "violation-brick-threshold" => self.violation_brick_threshold = value as u32,
+            "server-micro-alert" => self.server_micro_alert = value as u32,
+            _ => (),
+        }
+    }
+
+    /// The `temp-save-name` without its extension, which each check's filtered save is named after.
+    pub fn temp_save_stem(&self) -> &str {
+        self.temp_save_name
+            .strip_suffix(".brs")
+            .unwrap_or(&self.temp_save_name)
+    }
+
+    /// Whether or not a brick owner is on the ignore owners list.
+    pub fn is_ignored_owner(&self, id: Uuid) -> bool {
+        self.parsed_ignore_owners.contains(&id)
+    }
+
+    /// Whether or not a brick owner is on the exempt players list.
+    pub fn is_exempt(&self, owner: &BrickOwner) -> bool {
+        self.exempt_players.iter().any(|p| {
+            if p.id.is_empty() {
+                p.name.eq_ignore_ascii_case(&owner.name)
+            } else {
+                p.id.parse::<Uuid>() == Ok(owner.id)
+            }
+        })
+    }
+}
+
+/// Match a glob pattern, where `*` matches any run of characters and `?` matches any one.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            glob_match(&pattern[1..], text) || (!text.is_empty() && glob_match(pattern, &text[1..]))
+        }
+        (Some(b'?'), Some(_)) => glob_match(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => glob_match(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+/// Check a list of players for entries that can't be matched against anyone.
+fn check_players(issues: &mut Vec<Issue>, field: &str, players: &[AuthPlayer]) {
+    for (i, player) in players.iter().enumerate() {
+        if player.name.is_empty() && player.id.is_empty() {
+            issues.push(Issue::Fatal(format!(
+                "{} entry #{} has neither a name nor an id",
+                field,
+                i + 1
+            )));
+        } else if !player.id.is_empty() && player.id.parse::<Uuid>().is_err() {
+            issues.push(Issue::Fatal(format!(
+                "{} entry #{} ({}) has an invalid id \"{}\"",
+                field,
+                i + 1,
+                player.name,
+                player.id
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use brickadia::save::Header2;
+    use serde_json::{json, Value};
+
+    /// A config with only the required settings, plus any given in `settings`.
+    pub(crate) fn config(settings: Value) -> Config {
+        let mut value = json!({
+            "authorized": [],
+            "clear-after-minutes": 5,
+            "max-violations": 3,
+            "max-bans": 3,
+        });
+        for (key, setting) in settings.as_object().unwrap() {
+            value[key] = setting.clone();
+        }
+
+        let mut config: Config = serde_json::from_value(value).unwrap();
+        config.messages.fill_defaults();
+        config.parse_entries();
+        config
+    }
+
+    /// A header whose assets are named with every mix of case, like modded assets are.
+    fn header() -> Header2 {
+        Header2 {
+            brick_assets: [
+                "PB_DefaultBrick",
+                "PB_DefaultMicroBrick",
+                "pb_defaultmicrowedge",
+                "B_MICROWEDGE_Custom",
+                "B_ModLight",
+                "b_MicroPlate",
+            ]
+            .iter()
+            .map(|asset| asset.to_string())
+            .collect(),
+            ..Header2::default()
+        }
+    }
+
+    fn brick(asset: usize) -> Brick {
+        Brick {
+            asset_name_index: asset as u32,
+            ..Brick::default()
+        }
+    }
+
+    #[test]
+    fn asset_rules_ignore_case() {
+        let config = config(json!({
+            "banned-assets": ["MICRO"],
+            "rules": ["pb_MicroWedge=warn", "MicroPlate=instant-clear"],
+            "allowed-assets": ["b_microWEDGE_custom"],
+            "categories": ["lights=b_modlight"],
+        }));
+        let header = header();
+        let rules = config.asset_rules(&header.brick_assets);
+
+        let severities = (0..header.brick_assets.len())
+            .map(|i| config.brick_severity(&rules, &brick(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            severities,
+            [
+                None,
+                Some(Severity::Clear),
+                Some(Severity::Warn),
+                None,
+                None,
+                Some(Severity::InstantClear),
+            ]
+        );
+
+        let categories = (0..header.brick_assets.len())
+            .map(|i| config.brick_category(&rules, &brick(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(categories, [None, None, None, None, Some(0), None]);
+    }
+
+    #[test]
+    fn asset_rules_match_severity() {
+        let config = config(json!({ "rules": ["defaultMICROwedge=warn"] }));
+        let header = header();
+        let rules = config.asset_rules(&header.brick_assets);
+
+        for (i, asset) in header.brick_assets.iter().enumerate() {
+            assert_eq!(
+                config.brick_severity(&rules, &brick(i)),
+                config.severity(asset),
+                "{}",
+                asset
+            );
+            assert_eq!(
+                config.brick_category(&rules, &brick(i)),
+                config.category(asset),
+                "{}",
+                asset
+            );
+        }
+    }
+
+    #[test]
+    fn runtime_allowed_assets_ignore_case() {
+        let mut config = config(json!({}));
+        let header = header();
+        assert_eq!(
+            config.severity("PB_DefaultMicroBrick"),
+            Some(Severity::Clear)
+        );
+
+        config.set_runtime_allowed_assets(vec!["PB_DEFAULTMICRO".into()]);
+        let rules = config.asset_rules(&header.brick_assets);
+        assert_eq!(config.brick_severity(&rules, &brick(1)), None);
+        assert_eq!(config.brick_severity(&rules, &brick(2)), None);
+        assert_eq!(
+            config.brick_severity(&rules, &brick(5)),
+            Some(Severity::Clear)
+        );
+
+        config.set_runtime_allowed_assets(vec![]);
+        let rules = config.asset_rules(&header.brick_assets);
+        assert_eq!(
+            config.brick_severity(&rules, &brick(1)),
+            Some(Severity::Clear)
+        );
+    }
+}
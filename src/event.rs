@@ -0,0 +1,151 @@
+//! The `scan-summary` event emitted to other plugins after every check.
+//!
+//! The payload is the single argument of the event, a JSON object shaped like `ScanEvent`:
+//!
+//! ```json
+//! {
+//!     "version": 1,
+//!     "time": 1700000000,
+//!     "save": "data/Saved/Builds/autosave.brs",
+//!     "player": null,
+//!     "dryRun": false,
+//!     "bricks": 12345,
+//!     "owners": [{ "id": "...", "name": "x", "count": 40, "category": null }],
+//!     "warned": [{ "id": "...", "name": "x", "count": 40, "category": null }],
+//!     "cleared": [],
+//!     "bans": []
+//! }
+//! ```
+//!
+//! Dry runs report what would have been done, with `dryRun` set.
+//! Fields are only ever added to the payload. Anything renamed, removed, or given
+//! a different meaning bumps `version`.
+
+use std::path::Path;
+
+use chrono::Utc;
+use omegga::Omegga;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    config::{Config, LogLevel},
+    log,
+};
+
+/// The name of the event emitted to each of the config's `summary-plugins`.
+pub const SCAN_EVENT: &str = "scan-summary";
+
+/// The version of the `ScanEvent` payload.
+pub const SCAN_EVENT_VERSION: u32 = 1;
+
+/// What a check found and did, as sent to other plugins.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanEvent {
+    /// The version of this payload, see `SCAN_EVENT_VERSION`.
+    pub version: u32,
+
+    /// When the check started, as a unix timestamp.
+    pub time: u64,
+
+    /// The path of the save that was checked.
+    pub save: String,
+
+    /// The id of the only player the check covered, for checks of a single player.
+    pub player: Option<String>,
+
+    /// Whether or not the check was a dry run, so nothing was enforced.
+    pub dry_run: bool,
+
+    /// How many bricks the save has in total.
+    pub bricks: u32,
+
+    /// Every owner found with microbricks (or bricks of a category). `count` is how many they have.
+    pub owners: Vec<EventOwner>,
+
+    /// Owners warned about their microbricks, or pending a clear. `count` is how many they have.
+    pub warned: Vec<EventOwner>,
+
+    /// Owners whose bricks were cleared. `count` is how many microbricks were removed.
+    pub cleared: Vec<EventOwner>,
+
+    /// Owners banned by this check. `count` is their active violations.
+    pub bans: Vec<EventOwner>,
+}
+
+/// An owner in a `ScanEvent`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventOwner {
+    /// The owner's id.
+    pub id: String,
+
+    /// The owner's name.
+    pub name: String,
+
+    /// A count whose meaning depends on the list the owner is in.
+    pub count: u32,
+
+    /// The restricted category the entry is for, or null for microbricks.
+    pub category: Option<String>,
+}
+
+impl EventOwner {
+    pub fn new(id: Uuid, name: &str, count: u32, category: Option<&str>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            count,
+            category: category.map(str::to_string),
+        }
+    }
+}
+
+impl ScanEvent {
+    /// Start the event for a check of a save, filled in as the check goes.
+    pub fn new(path: &Path, only: Option<Uuid>) -> Self {
+        Self {
+            version: SCAN_EVENT_VERSION,
+            time: Utc::now().timestamp() as u64,
+            save: path.display().to_string(),
+            player: only.map(|id| id.to_string()),
+            dry_run: false,
+            bricks: 0,
+            owners: vec![],
+            warned: vec![],
+            cleared: vec![],
+            bans: vec![],
+        }
+    }
+}
+
+/// Emit a finished check's event to each of the config's `summary-plugins`.
+pub async fn emit(omegga: &Omegga, config: &Config, event: &ScanEvent) {
+    if config.summary_plugins.is_empty() {
+        return;
+    }
+
+    let payload = match serde_json::to_value(event) {
+        Ok(payload) => payload,
+        Err(e) => {
+            omegga.error(format!("failed to serialize scan summary: {}", e));
+            return;
+        }
+    };
+
+    for plugin in config.summary_plugins.iter() {
+        if omegga
+            .emit_plugin::<Value>(plugin.clone(), SCAN_EVENT.into(), vec![payload.clone()])
+            .await
+            .is_err()
+        {
+            log(
+                omegga,
+                config,
+                LogLevel::Debug,
+                format!("Couldn't emit scan summary to {}", plugin),
+            );
+        }
+    }
+}
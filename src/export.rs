@@ -0,0 +1,187 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use chrono::Utc;
+use omegga::Omegga;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::records::{
+    get_count, get_violations, Action, Clear, Pending, RecentClear, Sighting, Stats, Violation,
+};
+
+/// The directory exports are written to, relative to the plugin directory.
+pub const EXPORTS_DIR: &str = "exports";
+
+/// The version of the export format written by `/am export`.
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Every record in the store, as written by `/am export`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Export {
+    /// The version of the format, in case it ever changes.
+    pub version: u32,
+
+    /// When the export was made, as a unix timestamp.
+    pub time: u64,
+
+    /// Each store key and its value.
+    pub records: BTreeMap<String, Value>,
+}
+
+/// Read every record in the store.
+pub async fn collect(omegga: &Omegga) -> Result<Export> {
+    let mut records = BTreeMap::new();
+    for key in omegga.store_keys().await? {
+        if let Some(value) = omegga.store_get(key.clone()).await? {
+            records.insert(key, value);
+        }
+    }
+
+    Ok(Export {
+        version: EXPORT_VERSION,
+        time: Utc::now().timestamp() as u64,
+        records,
+    })
+}
+
+/// Write an export to a new timestamped file, returning its path.
+pub async fn write(export: &Export) -> Result<PathBuf> {
+    tokio::fs::create_dir_all(EXPORTS_DIR).await?;
+    let path = PathBuf::from(EXPORTS_DIR).join(format!(
+        "records_{}.json",
+        Utc::now().format("%Y%m%d_%H%M%S")
+    ));
+    tokio::fs::write(&path, serde_json::to_vec_pretty(export)?).await?;
+    Ok(path)
+}
+
+/// Read and validate an export, describing the first problem found if it's malformed.
+pub async fn read(path: &Path) -> Result<Export> {
+    let export: Export = serde_json::from_slice(&tokio::fs::read(path).await?)
+        .map_err(|e| anyhow!("not a valid export: {}", e))?;
+
+    if export.version != EXPORT_VERSION {
+        bail!(
+            "unsupported export version {} (expected {})",
+            export.version,
+            EXPORT_VERSION
+        );
+    }
+
+    for (key, value) in export.records.iter() {
+        check_record(key, value).map_err(|e| anyhow!("record {}: {}", key, e))?;
+    }
+
+    Ok(export)
+}
+
+/// The kind of record a store key holds, such as `violations` for `violations:{id}`.
+pub fn kind(key: &str) -> &str {
+    key.split_once(':').map_or(key, |(kind, _)| kind)
+}
+
+/// Check that a record's key is one the plugin uses, and that its value has the right shape.
+fn check_record(key: &str, value: &Value) -> Result<(), String> {
+    fn shape<T: serde::de::DeserializeOwned>(value: &Value) -> Result<(), String> {
+        serde_json::from_value::<T>(value.clone())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    // per-player records are keyed by id
+    // (timers, pending clears, and cooldowns of a category are keyed like `ts:lights:{id}`)
+    if let Some((kind, id)) = key.split_once(':') {
+        let id = match kind {
+            "ts" | "pending" | "hidden" | "lastclear" => id.rsplit(':').next().unwrap_or(id),
+            _ => id,
+        };
+        if id.parse::<Uuid>().is_err() {
+            return Err(format!("\"{}\" is not a valid id", id));
+        }
+
+        return match kind {
+            "ts" | "seen" | "lastseen" | "lastclear" | "permit" => match value {
+                Value::String(s) if s.parse::<u64>().is_ok() => Ok(()),
+                _ => Err("expected a timestamp string".into()),
+            },
+            "bans" | "kicks" => match value.as_u64() {
+                Some(_) => Ok(()),
+                None => Err("expected a count of at least 0".into()),
+            },
+            "violations" if value.is_u64() => Ok(()),
+            "violations" => shape::<Vec<Violation>>(value),
+            "history" => shape::<Vec<Clear>>(value),
+            "pending" => shape::<Pending>(value),
+            "sighting" => shape::<Sighting>(value),
+            "name" | "exempt" | "watch" => shape::<String>(value),
+            "notify" | "permitnotice" | "hidden" => shape::<bool>(value),
+            _ => Err(format!("unknown record type \"{}\"", kind)),
+        };
+    }
+
+    match key {
+        "stats" => shape::<Stats>(value),
+        "recent-clears" => shape::<Vec<RecentClear>>(value),
+        "actions" => shape::<Vec<Action>>(value),
+        "allowed-assets" => shape::<Vec<String>>(value),
+        "thresholds" => shape::<BTreeMap<String, f64>>(value),
+        "paused" | "dryrun" | "server-alert" => shape::<bool>(value),
+        "public-alert" => match value {
+            Value::String(s) if s.parse::<u64>().is_ok() => Ok(()),
+            _ => Err("expected a timestamp string".into()),
+        },
+        _ => Err("unknown record".into()),
+    }
+}
+
+/// Write an export's records into the store, returning how many of each kind were written.
+///
+/// With `replace`, every existing record is wiped first. Otherwise, records are merged:
+/// counters (violations, bans, and kicks) keep whichever is higher, and anything else
+/// is only imported if there's no existing record for it.
+pub async fn import(
+    omegga: &Omegga,
+    export: Export,
+    replace: bool,
+) -> Result<BTreeMap<String, usize>> {
+    if replace {
+        omegga.store_wipe();
+    }
+    let existing = if replace {
+        vec![]
+    } else {
+        omegga.store_keys().await?
+    };
+
+    let mut imported = BTreeMap::new();
+    for (key, value) in export.records {
+        if existing.contains(&key) {
+            let higher = match key.split_once(':') {
+                Some(("bans", _)) | Some(("kicks", _)) => {
+                    value.as_i64().unwrap_or(0) > get_count(omegga, key.clone()).await?
+                }
+                Some(("violations", id)) => {
+                    let count = match &value {
+                        Value::Array(violations) => violations.len() as u64,
+                        value => value.as_u64().unwrap_or(0),
+                    };
+                    count > get_violations(omegga, id).await?.len() as u64
+                }
+                _ => false,
+            };
+            if !higher {
+                continue;
+            }
+        }
+
+        *imported.entry(kind(&key).to_string()).or_insert(0) += 1;
+        omegga.store_set(key, value);
+    }
+
+    Ok(imported)
+}
@@ -0,0 +1,159 @@
+use crate::messages::Messages;
+
+/// How close, in save units, a brick must be to a cluster's center to join it.
+const CLUSTER_RADIUS: i64 = 500;
+
+/// How many clusters are listed in a message before the rest are summarized.
+const MAX_CLUSTERS: usize = 3;
+
+/// How wide a stud is, in save units.
+const STUD: f32 = 10.;
+
+/// The minimum and maximum corners of a box around some bricks.
+pub type Bounds = ((i32, i32, i32), (i32, i32, i32));
+
+/// A group of nearby bricks.
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    /// The sum of each brick's position, used to compute the center.
+    sum: (i64, i64, i64),
+
+    /// The smallest and largest coordinates of any brick in the cluster.
+    bounds: Bounds,
+
+    /// How many bricks are in the cluster.
+    pub count: u32,
+}
+
+impl Cluster {
+    /// The average position of the bricks in the cluster.
+    pub fn center(&self) -> (i32, i32, i32) {
+        let n = self.count.max(1) as i64;
+        (
+            (self.sum.0 / n) as i32,
+            (self.sum.1 / n) as i32,
+            (self.sum.2 / n) as i32,
+        )
+    }
+
+    /// The box around the bricks in the cluster.
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    /// How many bricks there are per cubic stud of the box around the cluster,
+    /// with each side counted as at least a stud long.
+    pub fn density(&self) -> f32 {
+        let ((x1, y1, z1), (x2, y2, z2)) = self.bounds;
+        let side = |a: i32, b: i32| ((b as f32 - a as f32) / STUD).max(1.);
+        self.count as f32 / (side(x1, x2) * side(y1, y2) * side(z1, z2))
+    }
+
+    fn distance_squared(&self, (x, y, z): (i32, i32, i32)) -> i64 {
+        let (cx, cy, cz) = self.center();
+        let (dx, dy, dz) = (
+            x as i64 - cx as i64,
+            y as i64 - cy as i64,
+            z as i64 - cz as i64,
+        );
+        dx * dx + dy * dy + dz * dz
+    }
+}
+
+/// Group brick positions into clusters, largest first.
+///
+/// Each position joins the first cluster whose center is within `CLUSTER_RADIUS`,
+/// otherwise it starts a new one.
+pub fn cluster(positions: &[(i32, i32, i32)]) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = vec![];
+    for &pos in positions {
+        match clusters
+            .iter_mut()
+            .find(|c| c.distance_squared(pos) <= CLUSTER_RADIUS * CLUSTER_RADIUS)
+        {
+            Some(c) => {
+                c.sum.0 += pos.0 as i64;
+                c.sum.1 += pos.1 as i64;
+                c.sum.2 += pos.2 as i64;
+                c.count += 1;
+                c.bounds = extend(c.bounds, pos);
+            }
+            None => clusters.push(Cluster {
+                sum: (pos.0 as i64, pos.1 as i64, pos.2 as i64),
+                bounds: (pos, pos),
+                count: 1,
+            }),
+        }
+    }
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.count));
+    clusters
+}
+
+/// Grow a box to include a position.
+fn extend((min, max): Bounds, (x, y, z): (i32, i32, i32)) -> Bounds {
+    (
+        (min.0.min(x), min.1.min(y), min.2.min(z)),
+        (max.0.max(x), max.1.max(y), max.2.max(z)),
+    )
+}
+
+/// The box around a set of bricks, or `None` if there are none.
+pub fn bounds(positions: &[(i32, i32, i32)]) -> Option<Bounds> {
+    let (&first, rest) = positions.split_first()?;
+    Some(
+        rest.iter()
+            .fold((first, first), |bounds, &pos| extend(bounds, pos)),
+    )
+}
+
+/// Describe the box around a set of bricks, with its corners and center.
+pub fn describe_bounds(messages: &Messages, positions: &[(i32, i32, i32)]) -> String {
+    let ((x1, y1, z1), (x2, y2, z2)) = match bounds(positions) {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    let center = |a: i32, b: i32| ((a as i64 + b as i64) / 2) as i32;
+
+    messages.text(
+        "location-bounds",
+        &[
+            ("x1", &x1),
+            ("y1", &y1),
+            ("z1", &z1),
+            ("x2", &x2),
+            ("y2", &y2),
+            ("z2", &z2),
+            ("x", &center(x1, x2)),
+            ("y", &center(y1, y2)),
+            ("z", &center(z1, z2)),
+        ],
+    )
+}
+
+/// Describe where a set of bricks are, listing the largest clusters and summarizing the rest.
+pub fn describe(messages: &Messages, positions: &[(i32, i32, i32)]) -> String {
+    let clusters = cluster(positions);
+    let mut parts = clusters
+        .iter()
+        .take(MAX_CLUSTERS)
+        .map(|c| {
+            let (x, y, z) = c.center();
+            messages.text(
+                "location",
+                &[("count", &c.count), ("x", &x), ("y", &y), ("z", &z)],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let rest = clusters
+        .iter()
+        .skip(MAX_CLUSTERS)
+        .map(|c| c.count)
+        .sum::<u32>();
+    if rest > 0 {
+        parts.push(messages.text("location-rest", &[("count", &rest)]));
+    }
+
+    parts.join(", ")
+}
@@ -1,55 +1,156 @@
 use std::{
-    collections::HashSet,
-    fs::{File, OpenOptions},
-    path::PathBuf,
-    time::Duration,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use anyhow::Result;
-use brickadia::{read::SaveReader, save::SaveData, write::SaveWriter};
+use anyhow::{bail, Result};
+use brickadia::{
+    read::SaveReader,
+    save::{Brick, BrickOwner, Header1, Header2, SaveData, Size},
+    util::{rotation, use_translation_table},
+    write::SaveWriter,
+};
 use chrono::Utc;
 use omegga::{events::Event, resources::Player, Omegga};
-use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
-pub const ASEZ: &str = "autosave_ez";
-pub const SAVES_LOC: &str = "../../data/Saved/Builds";
-pub const SAVE_LOC: &str = "_anti_microbrick.brs";
+use crate::{
+    config::{AssetRules, Channel, Config, Issue, LogLevel, Severity, VolumeMode},
+    event::{EventOwner, ScanEvent},
+    messages::{escape_command_arg, render, Messages},
+    records::{
+        active_violations, get_allowed_assets, get_count, get_exempt, get_history, get_pending,
+        get_permits, get_sighting, get_thresholds, get_timestamp, get_violations, get_watched,
+        is_dry_run, is_hidden, is_paused, log_action, set_hidden, set_history, set_last_seen,
+        set_name, set_pending, set_sighting, set_violations, update_recent_clears, update_stats,
+        wants_notifications, Action, ActionKind, Clear, Pending, RecentClear, Sighting, Spot,
+        Violation, PENDING_EXPIRY_MINUTES,
+    },
+};
 
-const PUBLIC_ID: Uuid = Uuid::from_bytes([0xff; 16]);
+mod commands;
+mod config;
+mod event;
+mod export;
+mod locations;
+mod messages;
+mod quarantine;
+mod records;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct AuthPlayer {
-    name: String,
-    id: String,
-}
+pub const BUILDS_DIR: &str = "data/Saved/Builds";
+pub const CONFIG_PATH: &str = "config.json";
+pub const MESSAGES_PATH: &str = "messages.json";
+
+/// Whether or not a check that can act on what it finds is running,
+/// like `/am scan` or the check of an autosave.
+static SCANNING: AtomicBool = AtomicBool::new(false);
+
+/// The latest autosave that arrived while `SCANNING`, to check once the running check finishes:
+/// its path, and its path relative to the builds directory.
+static DEFERRED_SAVE: Mutex<Option<(PathBuf, String)>> = Mutex::new(None);
+
+/// Whether or not a command that only reports what it finds, like `/am find`, is running.
+/// These never act on anyone, so they run alongside checks.
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// What the plugin has heard from autosave plugins and done with it since starting, for `/am info`.
+static STATUS: Mutex<Status> = Mutex::new(Status {
+    connected: None,
+    last_emit: None,
+    last_scan: None,
+});
 
-#[derive(Serialize, Deserialize)]
-struct Config {
-    authorized: Vec<AuthPlayer>,
+/// The plugin's status since starting.
+#[derive(Debug, Clone)]
+struct Status {
+    /// The autosave plugin that answered the connect handshake, and when, as a unix timestamp.
+    connected: Option<(String, u64)>,
 
-    #[serde(rename = "clear-after-minutes")]
-    clear_after: f32,
+    /// When an autosave plugin last emitted a save, as a unix timestamp.
+    last_emit: Option<u64>,
 
-    #[serde(rename = "max-violations")]
-    max_violations: u32,
+    /// When the last scan finished, as a unix timestamp, and what it found or why it failed.
+    last_scan: Option<(u64, Result<ScanSummary, String>)>,
+}
 
-    #[serde(rename = "ban-time")]
-    ban_time: f32,
+/// Get a copy of the plugin's status.
+fn status() -> Status {
+    STATUS.lock().unwrap().clone()
+}
 
-    #[serde(rename = "max-bans")]
-    max_bans: u32,
+/// Record the outcome of a finished scan.
+fn record_scan(result: &Result<ScanSummary>) {
+    let outcome = match result {
+        Ok(summary) => Ok(summary.clone()),
+        Err(e) => Err(e.to_string()),
+    };
+    STATUS.lock().unwrap().last_scan = Some((Utc::now().timestamp() as u64, outcome));
 }
 
+const PUBLIC_ID: Uuid = Uuid::from_bytes([0xff; 16]);
+const SERVER_ALERT_TOP_OWNERS: usize = 5;
+
+/// What the save taken right before clearing, to clear from, is named after.
+const CLEAR_SAVE_NAME: &str = "_anti_microbrick_clear";
+
+/// What the saves taken by `/am scan`, `/am check`, and `/am find` are named after.
+const SCAN_SAVE_NAME: &str = "_anti_microbrick_scan";
+
+/// The directory filtered saves are written to and loaded back in from, relative to the saves
+/// directory. Only the plugin writes to it, so leftovers can be swept without touching anyone's builds.
+const TEMP_DIR: &str = "anti_microbrick_temp";
+
+/// How long to wait on a save asked for with `save_now` while it doesn't show up or grow.
+const SAVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often `save_now` looks at the save it's waiting on.
+const SAVE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many looks in a row a save's size has to hold for `save_now` to consider it written.
+const SAVE_STABLE_POLLS: u32 = 3;
+
+/// How long after a public microbrick alert before another is sent, in seconds.
+const PUBLIC_ALERT_INTERVAL: u64 = 60 * 60;
+
+/// How many assets an owner's breakdown names before summarizing the rest.
+const ASSET_BREAKDOWN_LIMIT: usize = 3;
+
 #[tokio::main]
 async fn main() {
-    let config: Config = serde_json::from_reader(
-        File::open("config.json").expect("omegga did not emit a config file"),
-    )
-    .expect("failed to deserialize plugin config");
+    // load and validate the config up front, but hold on to any problems until
+    // the plugin has initialized so they show up in the web UI
+    let (config, issues) = match Config::load(CONFIG_PATH, MESSAGES_PATH) {
+        Ok(config) => {
+            let issues = config.validate();
+            if issues.iter().any(Issue::is_fatal) {
+                (None, issues)
+            } else {
+                (Some(config), issues)
+            }
+        }
+        Err(e) => (
+            None,
+            vec![Issue::Fatal(format!(
+                "failed to load {}: {}",
+                CONFIG_PATH, e
+            ))],
+        ),
+    };
 
-    let omegga = Omegga::new();
+    // the active config lives behind a lock so it can be swapped out by `/am reload`
+    let config = config.map(|config| Arc::new(RwLock::new(config)));
+
+    // shared so subcommands like `/am scan` can run in the background
+    let omegga = Arc::new(Omegga::new());
     let mut rx = omegga.spawn();
 
     while let Some(message) = rx.recv().await {
@@ -61,15 +162,60 @@ async fn main() {
                     None,
                 );
 
-                // when the plugin initializes, connect to asez. we will expect a "connected" request later on
-                omegga
-                    .emit_plugin::<u8>(ASEZ.into(), "connect".into(), vec![])
-                    .await
-                    .unwrap();
+                for issue in issues.iter() {
+                    match issue {
+                        Issue::Fatal(e) => omegga.error(format!("config error: {}", e)),
+                        Issue::Warning(w) => omegga.warn(format!("config warning: {}", w)),
+                    }
+                }
+
+                if config.is_none() {
+                    omegga.error("the plugin config is invalid, microbricks will not be enforced");
+                    continue;
+                }
+
+                // when the plugin initializes, connect to the autosave plugins. we will expect a "connected" request later on
+                if let Some(config) = &config {
+                    let mut config = config.write().await;
+                    match get_allowed_assets(&omegga).await {
+                        Ok(assets) => config.set_runtime_allowed_assets(assets),
+                        Err(e) => omegga.error(format!("failed to load allowed assets: {}", e)),
+                    }
+                    match get_thresholds(&omegga).await {
+                        Ok(thresholds) => {
+                            for e in config.apply_thresholds(&thresholds) {
+                                omegga.warn(format!("ignoring threshold override: {}", e));
+                            }
+                        }
+                        Err(e) => omegga.error(format!("failed to load thresholds: {}", e)),
+                    }
+                    purge_quarantine(&omegga, &config);
+                    purge_temp_saves(&omegga, &config);
+                    connect_autosave(&omegga, &config).await;
+                }
             }
             Event::Stop { id, .. } => {
                 omegga.write_response(id, None, None);
             }
+            Event::Join(player) => {
+                set_name(&omegga, &player.id, &player.name);
+                set_last_seen(&omegga, &player.id);
+                if let Err(e) = record_first_seen(&omegga, &player.id).await {
+                    omegga.error(format!("failed to record join: {}", e));
+                }
+
+                // let them know about a permit that expired while they were away
+                if let Some(config) = &config {
+                    let key = format!("permitnotice:{}", player.id);
+                    if let Ok(Some(_)) = omegga.store_get(key.clone()).await {
+                        omegga.store_delete(key).await;
+                        omegga.whisper(
+                            player.name,
+                            config.read().await.messages.get("permit-expired"),
+                        );
+                    }
+                }
+            }
             Event::Command {
                 player,
                 command,
@@ -79,72 +225,84 @@ async fn main() {
                     continue;
                 }
 
-                if !config
-                    .authorized
-                    .iter()
-                    .any(|p| p.name.eq_ignore_ascii_case(player.as_str()))
-                {
-                    continue;
-                }
+                let config = match &config {
+                    Some(config) => config,
+                    None => continue,
+                };
 
-                let subcommand = &args[0];
                 let players = match omegga.get_players().await {
                     Ok(p) => p,
                     Err(_) => continue,
                 };
 
-                match subcommand.as_str() {
-                    "clean" => {
-                        let target = args.into_iter().skip(1).collect::<String>().to_lowercase();
-                        let target = match players
-                            .iter()
-                            .find(|p| p.name.to_lowercase().starts_with(&target))
-                        {
-                            Some(p) => p,
-                            None => {
-                                omegga
-                                    .whisper(player, format!("Please specify a player to clean."));
-                                continue;
-                            }
-                        };
-
-                        omegga.store_delete(format!("ts:{}", target.id)).await;
-                        omegga
-                            .store_delete(format!("violations:{}", target.id))
-                            .await;
-                        omegga.store_delete(format!("bans:{}", target.id)).await;
-
-                        omegga.whisper(
-                            player,
-                            format!("Cleared <b>{}</>'s record, if any.", target.name),
-                        );
-                    }
-                    "wipe" => match args.get(1) {
-                        Some(s) if s.as_str() == "yes" => {
-                            omegga.store_wipe();
-                            omegga.whisper(player, "OK, all records wiped.");
+                // resolve the invoking player so they can be authorized by their id
+                match players.iter().find(|p| p.name == player) {
+                    Some(p) if config.read().await.is_authorized(p) => (),
+                    _ => {
+                        let config = config.read().await;
+                        if config.reply_unauthorized {
+                            omegga.whisper(player, config.messages.get("command-unauthorized"));
                         }
-                        _ => {
-                            omegga.whisper(player, format!("<b>Are you sure you wish to wipe all records?</> Please run <code>/am wipe yes</> to confirm."));
-                        }
-                    },
-                    x => omegga.whisper(player, format!("Invalid subcommand <code>/am {}</>.", x)),
+                        continue;
+                    }
                 }
+
+                commands::handle(&omegga, config, &players, player, args).await;
             }
             Event::PluginEmit {
                 id,
                 event,
                 from,
                 args,
-            } => match (from.as_str(), event.as_str()) {
-                (ASEZ, "save") => {
+            } => match event.as_str() {
+                "save" if is_autosave_plugin(&config, &from).await => {
                     let save_path = args[0].as_str().unwrap();
                     let mut path = PathBuf::from("../..");
                     path.push(save_path);
                     omegga.write_response(id, None, None);
-                    if let Err(e) = check_save(&omegga, &config, path).await {
-                        omegga.error(format!("failed to check save: {}", e));
+                    STATUS.lock().unwrap().last_emit = Some(Utc::now().timestamp() as u64);
+
+                    let config = match &config {
+                        Some(config) => config,
+                        None => continue,
+                    };
+
+                    // match ignore patterns against the path relative to the builds directory
+                    let relative = Path::new(save_path)
+                        .strip_prefix(BUILDS_DIR)
+                        .unwrap_or_else(|_| Path::new(save_path))
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    // while another check is running, this save waits for it to finish
+                    // (only the latest save waits, since it covers everything before it)
+                    let deferred = {
+                        let mut deferred = DEFERRED_SAVE.lock().unwrap();
+                        let running = SCANNING.swap(true, Ordering::SeqCst);
+                        if running {
+                            *deferred = Some((path.clone(), relative.clone()));
+                        }
+                        running
+                    };
+                    if deferred {
+                        log(
+                            &omegga,
+                            &*config.read().await,
+                            LogLevel::Normal,
+                            format!(
+                                "A check is already running, checking save {} once it finishes",
+                                relative
+                            ),
+                        );
+                        continue;
                     }
+
+                    // check in the background, since clearing waits on the game to save,
+                    // and commands and events shouldn't wait on that
+                    let (omegga, config) = (Arc::clone(&omegga), Arc::clone(config));
+                    tokio::spawn(async move {
+                        check_autosave(&omegga, &config, path, &relative).await;
+                        finish_scanning(&omegga, &config).await;
+                    });
                 }
                 _ => omegga.write_response(id, None, None),
             },
@@ -153,204 +311,2596 @@ async fn main() {
     }
 }
 
-async fn check_save(omegga: &Omegga, config: &Config, path: PathBuf) -> Result<()> {
-    let mut reader = SaveReader::new(File::open(path)?)?;
-    let header1 = reader.read_header1()?;
-    let header2 = reader.read_header2()?;
+/// Let other checks run again once one finishes, after checking the autosave
+/// that arrived while it ran, if any.
+async fn finish_scanning(omegga: &Omegga, config: &RwLock<Config>) {
+    loop {
+        // (the flag is only let go of under the lock, so no autosave can be deferred after it)
+        let (path, relative) = {
+            let mut deferred = DEFERRED_SAVE.lock().unwrap();
+            match deferred.take() {
+                Some(save) => save,
+                None => {
+                    SCANNING.store(false, Ordering::SeqCst);
+                    return;
+                }
+            }
+        };
+        check_autosave(omegga, config, path, &relative).await;
+    }
+}
 
-    let players = omegga.get_players().await?;
+/// Check a save written by one of the autosave plugins, unless it's ignored.
+async fn check_autosave(omegga: &Omegga, config: &RwLock<Config>, path: PathBuf, relative: &str) {
+    // check against a copy of the config, so commands that change it don't wait on the check
+    let config = config.read().await.clone();
+    if config.is_ignored_save(relative) {
+        log(
+            omegga,
+            &config,
+            LogLevel::Normal,
+            format!("Skipping ignored save {}", relative),
+        );
+        return;
+    }
 
-    // expect there to be no microbricks
-    if !header2
-        .brick_assets
-        .iter()
-        .any(|asset| asset.contains("Micro"))
-    {
-        // there are no microbricks! we can safely stop checking this save
-        return Ok(());
+    log(
+        omegga,
+        &config,
+        LogLevel::Debug,
+        format!("Checking save {}", relative),
+    );
+    let start = Instant::now();
+    let result = check_save(omegga, &config, path, None).await;
+    record_scan(&result);
+    if let Err(e) = result {
+        omegga.error(format!("failed to check save: {}", e));
     }
+    log(
+        omegga,
+        &config,
+        LogLevel::Debug,
+        format!(
+            "Finished checking save {} in {}ms",
+            relative,
+            start.elapsed().as_millis()
+        ),
+    );
+}
 
-    // at this point, we know we have microbricks, so let's scan the save for them
-    reader.skip_preview()?;
-    let (mut bricks, components) = reader.read_bricks(&header1, &header2)?;
+/// Connect to each autosave plugin in the config, warning if none of them respond.
+async fn connect_autosave(omegga: &Omegga, config: &Config) {
+    let mut connected = false;
+    for plugin in config.autosave_plugins.iter() {
+        match omegga
+            .emit_plugin::<Value>(plugin.clone(), "connect".into(), vec![])
+            .await
+        {
+            Ok(_) if !connected => {
+                connected = true;
+                STATUS.lock().unwrap().connected =
+                    Some((plugin.clone(), Utc::now().timestamp() as u64));
+                log(
+                    omegga,
+                    config,
+                    LogLevel::Normal,
+                    format!("Connected to autosave plugin {}", plugin),
+                );
+            }
+            Ok(_) => (),
+            Err(_) => log(
+                omegga,
+                config,
+                LogLevel::Debug,
+                format!("Couldn't connect to autosave plugin {}", plugin),
+            ),
+        }
+    }
 
-    let mut micro_owners = HashSet::new();
-    let mut cleared_owners = HashSet::new();
+    if !connected {
+        STATUS.lock().unwrap().connected = None;
+        omegga.warn(format!(
+            "none of the autosave plugins ({}) responded, saves will not be checked until one is running",
+            config.autosave_plugins.join(", ")
+        ));
+    }
+}
 
-    for brick in bricks.iter() {
-        let asset = header2.brick_assets[brick.asset_name_index as usize].as_str();
-        if asset.contains("Micro") {
-            // this is a microbrick! figure out who owns it
-            let owner = match brick.owner_index {
-                0 => continue,
-                n => &header2.brick_owners[n as usize - 1],
+/// Delete quarantined saves older than `quarantine-retention-days`, if it's set.
+fn purge_quarantine(omegga: &Omegga, config: &Config) {
+    if config.quarantine_retention_days <= 0. {
+        return;
+    }
+
+    match quarantine::purge(config, config.quarantine_retention_days) {
+        Ok((count, bytes)) => log(
+            omegga,
+            config,
+            LogLevel::Normal,
+            format!(
+                "Purged {} quarantined saves older than {} days ({:.1} MB)",
+                count,
+                config.quarantine_retention_days,
+                bytes as f64 / 1024. / 1024.
+            ),
+        ),
+        Err(e) => omegga.error(format!("failed to purge quarantined saves: {}", e)),
+    }
+}
+
+/// Delete filtered saves left behind by checks whose reload never finished,
+/// and saves the plugin had the game take that were never read.
+///
+/// Only saves named by the plugin are touched: those in `TEMP_DIR` named after `temp-save-name`,
+/// and those in the saves directory named after `CLEAR_SAVE_NAME` or `SCAN_SAVE_NAME`.
+fn purge_temp_saves(omegga: &Omegga, config: &Config) {
+    let saves_dir = Path::new(&config.saves_dir);
+    let temp_prefix = format!("{}_", config.temp_save_stem());
+    let clear_prefix = format!("{}_", CLEAR_SAVE_NAME);
+    let scan_prefix = format!("{}_", SCAN_SAVE_NAME);
+    let sweeps = [
+        (saves_dir.join(TEMP_DIR), vec![temp_prefix.as_str()]),
+        (
+            saves_dir.to_path_buf(),
+            vec![clear_prefix.as_str(), scan_prefix.as_str()],
+        ),
+    ];
+
+    let mut count = 0;
+    for (dir, prefixes) in sweeps.iter() {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                omegga.error(format!("failed to purge leftover temp saves: {}", e));
+                continue;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let leftover =
+                name.ends_with(".brs") && prefixes.iter().any(|prefix| name.starts_with(prefix));
+            if leftover && fs::remove_file(entry.path()).is_ok() {
+                count += 1;
+            }
+        }
+    }
+
+    if count > 0 {
+        log(
+            omegga,
+            config,
+            LogLevel::Normal,
+            format!("Deleted {} leftover temp saves", count),
+        );
+    }
+}
+
+/// Whether or not a plugin is one of the configured autosave plugins.
+async fn is_autosave_plugin(config: &Option<Arc<RwLock<Config>>>, plugin: &str) -> bool {
+    match config {
+        Some(config) => config
+            .read()
+            .await
+            .autosave_plugins
+            .iter()
+            .any(|p| p == plugin),
+        None => false,
+    }
+}
+
+/// The microbricks a single owner has in a save, or their bricks of one of the categories.
+struct OwnerMicros<'a> {
+    owner: &'a BrickOwner,
+
+    /// The category these bricks fall under, or `None` for microbricks.
+    category: Option<usize>,
+
+    /// How many microbricks they have.
+    count: u32,
+
+    /// How many bricks they have in total, microbricks or not.
+    bricks: u32,
+
+    /// The total volume of their microbricks, in micro-units.
+    volume: u64,
+
+    /// The most severe rule any of their microbricks fall under.
+    severity: Severity,
+
+    /// Where each of their microbricks is.
+    positions: Vec<(i32, i32, i32)>,
+
+    /// How many of their microbricks are of each asset.
+    assets: HashMap<&'a str, u32>,
+
+    /// How many of their microbricks are invisible.
+    hidden: u32,
+
+    /// How many of their microbricks are buried inside a larger brick, with `detect-concealed`.
+    concealed: u32,
+}
+
+impl OwnerMicros<'_> {
+    /// Whether or not most of their microbricks are invisible, which is never an accident.
+    fn mostly_hidden(&self) -> bool {
+        self.hidden * 2 > self.count
+    }
+
+    /// Whether or not most of their microbricks are buried inside larger bricks.
+    fn mostly_concealed(&self) -> bool {
+        self.concealed * 2 > self.count
+    }
+
+    /// What percentage of their bricks are microbricks.
+    fn percent(&self) -> f32 {
+        self.count as f32 * 100. / self.bricks.max(1) as f32
+    }
+
+    /// What their bricks are called in logs: microbricks, or the name of their category.
+    fn kind<'c>(&self, config: &'c Config) -> &'c str {
+        match self.category {
+            Some(category) => &config.parsed_categories[category].name,
+            None => "microbricks",
+        }
+    }
+
+    /// The budget for their bricks, `max-micro-count` or their category's.
+    fn max_count(&self, config: &Config) -> u32 {
+        match self.category {
+            Some(category) => config.parsed_categories[category].max_count,
+            None => config.max_micro_count,
+        }
+    }
+
+    /// Which threshold their microbricks cross, if any: their budget (alongside
+    /// `max-micro-volume`), or `micro-percent-threshold` and `max-micro-density` when enabled.
+    /// Along with how many microbricks they're over by.
+    ///
+    /// Categories only have a budget.
+    fn threshold(&self, config: &Config) -> Option<(Threshold, u32)> {
+        let over_count = self.count.saturating_sub(self.max_count(config));
+        if self.category.is_none() && config.max_micro_volume > 0 {
+            // a volume threshold can't say which microbricks are over, so all of them are
+            let over_volume = self.volume > config.max_micro_volume;
+            let crossed = match config.micro_volume_mode {
+                VolumeMode::Either => over_volume,
+                VolumeMode::Both => over_volume && over_count > 0,
             };
+            if crossed {
+                let over = if over_count > 0 {
+                    over_count
+                } else {
+                    self.count
+                };
+                return Some((Threshold::Volume, over));
+            }
+        }
+
+        let count_applies = self.category.is_some()
+            || config.max_micro_volume == 0
+            || config.micro_volume_mode == VolumeMode::Either;
+        if over_count > 0 && count_applies {
+            return Some((Threshold::Count, over_count));
+        }
+
+        if self.category.is_none()
+            && config.micro_percent_threshold > 0.
+            && self.percent() > config.micro_percent_threshold
+        {
+            let allowed = (self.bricks as f32 * config.micro_percent_threshold / 100.) as u32;
+            return Some((Threshold::Percent, self.count.saturating_sub(allowed)));
+        }
 
-            if micro_owners.contains(&owner.id)
-                || cleared_owners.contains(&owner.id)
-                || owner.id == PUBLIC_ID
+        if self.category.is_none() && config.max_micro_density > 0. {
+            if let Some((density, count)) = self
+                .densest()
+                .filter(|&(density, _)| density > config.max_micro_density)
             {
-                continue;
+                return Some((Threshold::Density(density), count));
             }
+        }
 
-            // check if a timestamp has already been set for them
-            match omegga.store_get(format!("ts:{}", owner.id)).await? {
-                Some(Value::String(s)) => {
-                    // check if timer has expired
-                    // if it has, clear bricks
-                    // otherwise, warn the player
-                    let ts: u64 = s.parse()?;
-                    let now = Utc::now().timestamp() as u64;
-
-                    if now >= ts + (config.clear_after * 60.) as u64 {
-                        // clear bricks
-                        omegga.broadcast(format!(
-                            "Clearing <color=\"ff0\">{}</>'s microbricks...",
-                            owner.name
-                        ));
-                        cleared_owners.insert(owner.id);
-                    } else {
-                        // warn the player
-                        micro_owners.insert(owner.id);
-                        warn_player(omegga, &players, owner.id);
-                    }
-                }
-                _ => {
-                    // set the timestamp and warn
-                    let ts = Utc::now().timestamp() as u64;
-
-                    // if the clear_after amount is 0, just immediately clear bricks
-                    if config.clear_after == 0. {
-                        omegga.broadcast(format!(
-                            "Clearing <color=\"ff0\">{}</>'s microbricks...",
-                            owner.name
-                        ));
-                        cleared_owners.insert(owner.id);
-                    } else {
-                        micro_owners.insert(owner.id);
-                        omegga.store_set(format!("ts:{}", owner.id), Value::String(ts.to_string()));
-                        warn_player(omegga, &players, owner.id);
+        None
+    }
+
+    /// The densest of their clusters with enough microbricks to judge,
+    /// as its density and how many microbricks are in it.
+    fn densest(&self) -> Option<(f32, u32)> {
+        locations::cluster(&self.positions)
+            .iter()
+            .filter(|c| c.count >= DENSE_CLUSTER_MIN_COUNT)
+            .map(|c| (c.density(), c.count))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+/// How many microbricks a cluster needs before its density is judged,
+/// so a handful of microbricks next to each other isn't mistaken for a sculpture.
+const DENSE_CLUSTER_MIN_COUNT: u32 = 50;
+
+/// Which threshold an owner's microbricks crossed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Threshold {
+    /// They have more than `max-micro-count`.
+    Count,
+
+    /// Their microbricks add up to more than `max-micro-volume`.
+    Volume,
+
+    /// More than `micro-percent-threshold` of their bricks are microbricks.
+    Percent,
+
+    /// One of their clusters is denser than `max-micro-density`, at this many per cubic stud.
+    Density(f32),
+}
+
+impl Threshold {
+    /// Describe the threshold for a warning's `{reason}`, given how many microbricks they're over.
+    fn describe(self, config: &Config, micros: &OwnerMicros, over: u32) -> String {
+        let count = format_count(over as u64);
+        match self {
+            Threshold::Count => config.messages.text(
+                "threshold-count",
+                &[
+                    ("count", &count),
+                    ("max", &format_count(micros.max_count(config) as u64)),
+                ],
+            ),
+            Threshold::Volume => config.messages.text(
+                "threshold-volume",
+                &[
+                    ("count", &count),
+                    ("volume", &format_count(micros.volume)),
+                    ("max", &format_count(config.max_micro_volume)),
+                ],
+            ),
+            Threshold::Percent => config.messages.text(
+                "threshold-percent",
+                &[
+                    ("count", &count),
+                    ("percent", &format!("{:.1}", micros.percent())),
+                    ("max", &config.micro_percent_threshold),
+                ],
+            ),
+            Threshold::Density(density) => config.messages.text(
+                "threshold-density",
+                &[
+                    ("count", &count),
+                    ("density", &format!("{:.1}", density)),
+                    ("max", &config.max_micro_density),
+                ],
+            ),
+        }
+    }
+}
+
+/// The microbricks found in a save, before anything is done about them.
+struct Analysis<'a> {
+    /// Each owner's microbricks.
+    owners: HashMap<Uuid, OwnerMicros<'a>>,
+
+    /// Each owner's bricks of each category, by category index.
+    categories: HashMap<(Uuid, usize), OwnerMicros<'a>>,
+
+    /// How many public microbricks there are of each asset.
+    public: HashMap<&'a str, u32>,
+
+    /// Where each public microbrick is.
+    public_positions: Vec<(i32, i32, i32)>,
+
+    /// How many microbricks each running minigame owns.
+    minigames: HashMap<&'a String, u32>,
+
+    /// The indices of microbricks left alone for being in a cluster under `min-cluster-size`.
+    stragglers: HashSet<usize>,
+}
+
+/// How close two microbricks of the same owner can be to end up in the same cluster, in save units.
+const CLUSTER_DISTANCE: i32 = 20;
+
+/// Group each owner's microbricks into clusters of bricks near each other,
+/// returning the indices of every microbrick in a cluster smaller than `min-cluster-size`.
+///
+/// Bricks are bucketed into a grid, and neighbouring cells with bricks are joined together.
+fn find_stragglers(config: &Config, rules: &AssetRules, bricks: &[Brick]) -> HashSet<usize> {
+    if config.min_cluster_size <= 1 {
+        return HashSet::new();
+    }
+
+    // bucket each owner's microbricks into grid cells
+    let mut cells: HashMap<(u32, i32, i32, i32), Vec<usize>> = HashMap::new();
+    for (i, brick) in bricks.iter().enumerate() {
+        if config.brick_severity(rules, brick).is_none() {
+            continue;
+        }
+
+        let (x, y, z) = brick.position;
+        let cell = (
+            brick.owner_index,
+            x.div_euclid(CLUSTER_DISTANCE),
+            y.div_euclid(CLUSTER_DISTANCE),
+            z.div_euclid(CLUSTER_DISTANCE),
+        );
+        cells.entry(cell).or_default().push(i);
+    }
+
+    // join neighbouring cells into clusters
+    let keys = cells.keys().copied().collect::<Vec<_>>();
+    let index: HashMap<_, _> = keys.iter().enumerate().map(|(i, &k)| (k, i)).collect();
+    let mut parents = (0..keys.len()).collect::<Vec<_>>();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    for (i, &(owner, x, y, z)) in keys.iter().enumerate() {
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(&j) = index.get(&(owner, x + dx, y + dy, z + dz)) {
+                        let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                        parents[a] = b;
                     }
                 }
             }
         }
     }
 
-    // clear violator bricks
-    for id in cleared_owners.iter() {
-        omegga.clear_bricks(id.to_string(), true);
+    let mut sizes: HashMap<usize, u32> = HashMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        *sizes.entry(root(&mut parents, i)).or_insert(0) += cells[key].len() as u32;
+    }
 
-        let key = format!("violations:{}", id);
-        let mut violations: i64 = omegga
-            .store_get(key.clone())
-            .await?
-            .map(|v| v.as_i64().unwrap())
-            .unwrap_or(0);
-        violations += 1;
-
-        omegga.log(format!(
-            "Clearing bricks of {} ({} violations)",
-            id, violations
-        ));
+    keys.iter()
+        .enumerate()
+        .filter(|&(i, _)| sizes[&root(&mut parents, i)] < config.min_cluster_size)
+        .flat_map(|(_, key)| cells[key].iter().copied())
+        .collect()
+}
 
-        omegga.store_set(key, violations.into());
+/// How large a brick has to be, in every dimension, for microbricks to be hidden inside it.
+/// This is the size of a 2x2 brick, in save units.
+const CONCEALING_SIZE: u32 = 10;
 
-        if violations as u32 > config.max_violations {
-            // we've hit max violations: start banning the user
-            let key = format!("bans:{}", id);
-            let mut bans: i64 = omegga
-                .store_get(key.clone())
-                .await?
-                .map(|v| v.as_i64().unwrap())
-                .unwrap_or(0);
-            bans += 1;
+/// How large each cell of the grid used to look up concealing bricks is, in save units.
+const CONCEALING_CELL_SIZE: i32 = 200;
 
-            omegga.store_set(key, bans.into());
+/// The world space bounds of a procedural brick, taking its direction and rotation into account.
+fn brick_bounds(brick: &Brick) -> Option<locations::Bounds> {
+    let (x, y, z) = match brick.size {
+        Size::Procedural(x, y, z) => (x as i32, y as i32, z as i32),
+        Size::Empty => return None,
+    };
 
-            if bans as u32 > config.max_bans {
-                // permanently ban
-                omegga.writeln(format!(
-                    "Chat.Command /Ban {} {} \"Microbricks are not allowed on this server.\"",
-                    id, "-1",
-                ));
-            } else {
-                // temporarily ban
-                omegga.writeln(format!(
-                    "Chat.Command /Ban {} {} \"Microbricks are not allowed on this server. This ban will be permanent in {} more violations.\"",
-                    id,
-                    config.ban_time,
-                    config.max_bans - bans as u32,
-                ));
+    // brickadia's own table says which world axis each side ends up along
+    let orientation = rotation::d2o(brick.direction.clone() as u8, brick.rotation.clone() as u8);
+    let (x, y, z) = use_translation_table((x, y, z), orientation);
+    let (x, y, z) = (x.abs(), y.abs(), z.abs());
+
+    let (px, py, pz) = brick.position;
+    Some(((px - x, py - y, pz - z), (px + x, py + y, pz + z)))
+}
+
+/// Find the indices of every microbrick buried inside a larger brick, whoever owns it.
+///
+/// Large bricks are bucketed into a coarse grid by their bounds, so each microbrick
+/// is only checked against the large bricks near it.
+fn find_concealed(config: &Config, rules: &AssetRules, bricks: &[Brick]) -> HashSet<usize> {
+    let cell = |n: i32| n.div_euclid(CONCEALING_CELL_SIZE);
+
+    let mut micros = vec![];
+    let mut grid: HashMap<(i32, i32, i32), Vec<locations::Bounds>> = HashMap::new();
+    for (i, brick) in bricks.iter().enumerate() {
+        if config.brick_severity(rules, brick).is_some() {
+            micros.push(i);
+            continue;
+        }
+
+        let large =
+            matches!(brick.size, Size::Procedural(x, y, z) if x.min(y).min(z) >= CONCEALING_SIZE);
+        let bounds = match brick_bounds(brick) {
+            Some(bounds) if large => bounds,
+            _ => continue,
+        };
+
+        let (min, max) = bounds;
+        for x in cell(min.0)..=cell(max.0) {
+            for y in cell(min.1)..=cell(max.1) {
+                for z in cell(min.2)..=cell(max.2) {
+                    grid.entry((x, y, z)).or_default().push(bounds);
+                }
             }
-        } else {
-            omegga.whisper(
-                id.to_string(),
-                format!(
-                    "<b>You currently have {} microbrick violations. After {}, you will be temporarily banned.</>",
-                    violations,
-                    config.max_violations
-                )
-            );
         }
     }
 
-    // now, we should have a list of users whose bricks are cleared
-    // filter out bricks that were NOT placed by someone in this microbrick array
-    bricks.retain(|b| {
-        b.owner_index > 0
-            && cleared_owners.contains(&header2.brick_owners[b.owner_index as usize - 1].id)
-    });
+    micros
+        .into_iter()
+        .filter(|&i| {
+            let (x, y, z) = bricks[i].position;
+            grid.get(&(cell(x), cell(y), cell(z))).is_some_and(|large| {
+                large.iter().any(|&(min, max)| {
+                    min.0 < x && x < max.0 && min.1 < y && y < max.1 && min.2 < z && z < max.2
+                })
+            })
+        })
+        .collect()
+}
 
-    // now keep only bricks without "Micro" in their asset name
-    bricks.retain(|b| !header2.brick_assets[b.asset_name_index as usize].contains("Micro"));
+/// Count up the microbricks in a save by owner and asset, without acting on any of them.
+///
+/// With `only` set, every other owner (and public bricks) is skipped. Ignored owners are skipped
+/// too, and bricks owned by one of `minigames` are only counted per minigame.
+/// Microbricks in clusters under `min-cluster-size` aren't counted at all.
+/// With `detect-concealed`, microbricks buried inside larger bricks are counted as concealed.
+fn analyze_bricks<'a>(
+    config: &Config,
+    header2: &'a Header2,
+    bricks: &[Brick],
+    only: Option<Uuid>,
+    minigames: &[String],
+) -> Analysis<'a> {
+    let rules = config.asset_rules(&header2.brick_assets);
+    let mut analysis = Analysis {
+        owners: HashMap::new(),
+        categories: HashMap::new(),
+        public: HashMap::new(),
+        public_positions: vec![],
+        minigames: HashMap::new(),
+        stragglers: find_stragglers(config, &rules, bricks),
+    };
 
-    // now we've filtered out the bricks, so we can load everything back in as is
-    let save_data = SaveData {
-        header1,
-        header2,
-        bricks,
-        components,
-        ..Default::default()
+    let concealed = if config.detect_concealed {
+        find_concealed(config, &rules, bricks)
+    } else {
+        HashSet::new()
     };
 
-    SaveWriter::new(
-        OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(format!("{}/{}", SAVES_LOC, SAVE_LOC))?,
-        save_data,
-    )
-    .write()?;
+    // every owner's total brick count, by owner index, to weigh their microbricks against
+    let mut totals = vec![0u32; header2.brick_owners.len() + 1];
 
-    // artificial delay: we are literally too fast for brickadia
-    tokio::time::sleep(Duration::from_secs(1)).await;
+    // count up the microbricks (and category bricks) each owner has placed
+    // along with the most severe rule their bricks fall under and where they are
+    for (i, brick) in bricks.iter().enumerate() {
+        if let Some(total) = totals.get_mut(brick.owner_index as usize) {
+            *total += 1;
+        }
 
-    // load it into the game
-    omegga.load_bricks(SAVE_LOC, true, (0, 0, 0)).await?;
+        let asset = header2.brick_assets[brick.asset_name_index as usize].as_str();
+        let (severity, category) = match config.brick_severity(&rules, brick) {
+            Some(_) if analysis.stragglers.contains(&i) => continue,
+            Some(severity) => (severity, None),
+            None => match config.brick_category(&rules, brick) {
+                Some(category) => (Severity::Clear, Some(category)),
+                None => continue,
+            },
+        };
 
-    // at this point check if there are users with a timestamp that were not found in this scan
-    let keys = omegga.store_keys().await?;
-    for key in keys.iter().filter_map(|key| key.strip_prefix("ts:")) {
-        // if we didn't pick them up,
-        let parsed = key.parse()?;
-        if cleared_owners.contains(&parsed) || !micro_owners.contains(&parsed) {
-            // get em outta here
-            omegga.store_delete(format!("ts:{}", key)).await;
+        // this is a microbrick! figure out who owns it
+        let owner = match brick.owner_index {
+            0 => None,
+            n => Some(&header2.brick_owners[n as usize - 1]),
+        };
+
+        // only microbricks are enforced on public and minigame bricks
+        let owner = match owner {
+            Some(owner) if only.is_some_and(|id| id != owner.id) => continue,
+            Some(owner) if owner.id != PUBLIC_ID => owner,
+            _ if only.is_some() || category.is_some() => continue,
+            _ => {
+                *analysis.public.entry(asset).or_insert(0) += 1;
+                analysis.public_positions.push(brick.position);
+                continue;
+            }
+        };
+
+        // ignored owners are treated like public bricks, minus any enforcement
+        if config.is_ignored_owner(owner.id) {
+            continue;
+        }
+
+        if minigames.contains(&owner.name) {
+            if category.is_none() {
+                *analysis.minigames.entry(&owner.name).or_insert(0) += 1;
+            }
+            continue;
         }
+
+        let new = || OwnerMicros {
+            owner,
+            category,
+            count: 0,
+            bricks: 0,
+            volume: 0,
+            severity,
+            positions: vec![],
+            assets: HashMap::new(),
+            hidden: 0,
+            concealed: 0,
+        };
+        let entry = match category {
+            Some(category) => analysis
+                .categories
+                .entry((owner.id, category))
+                .or_insert_with(new),
+            None => analysis.owners.entry(owner.id).or_insert_with(new),
+        };
+        entry.count += 1;
+        entry.volume += micro_volume(brick);
+        entry.hidden += !brick.visibility as u32;
+        entry.concealed += concealed.contains(&i) as u32;
+        entry.severity = entry.severity.max(severity);
+        entry.positions.push(brick.position);
+        *entry.assets.entry(asset).or_insert(0) += 1;
     }
 
-    Ok(())
+    for (owner, total) in header2.brick_owners.iter().zip(totals.into_iter().skip(1)) {
+        if let Some(micros) = analysis.owners.get_mut(&owner.id) {
+            micros.bricks += total;
+        }
+    }
+    for ((id, _), bricks) in analysis.categories.iter_mut() {
+        bricks.bricks = analysis.owners.get(id).map_or(0, |micros| micros.bricks);
+    }
+
+    analysis
+}
+
+/// The volume of a microbrick in micro-units, where a 1x1x1 microbrick is 1.
+///
+/// Non-procedural bricks get their size from their asset, so they count as one.
+fn micro_volume(brick: &Brick) -> u64 {
+    match brick.size {
+        Size::Procedural(x, y, z) => (x as u64 * y as u64 * z as u64).max(1),
+        Size::Empty => 1,
+    }
 }
 
-fn warn_player(omegga: &Omegga, players: &[Player], target: impl ToString) {
-    let target = target.to_string();
+/// Filter a save down to the bricks reloaded after a clear: those of everyone cleared
+/// (and public bricks, with `clear_public`), minus the bricks they were cleared for.
+///
+/// In convert mode, cleared microbricks are swapped over to `convert-asset` instead, and only
+/// dropped if they're still undersized. Owners left without any bricks are dropped too.
+///
+/// Components travel with their bricks, since the save writer rebuilds each component's
+/// brick indices from the bricks it writes.
+fn filter_cleared(
+    config: &Config,
+    header2: &mut Header2,
+    bricks: &mut Vec<Brick>,
+    cleared_owners: &HashMap<(Uuid, Option<usize>), ClearedOwner>,
+    clear_public: bool,
+    stragglers: &HashSet<usize>,
+) {
+    // filter out bricks that were NOT placed by someone in this microbrick array
+    // (public bricks are kept too if they were cleared, so their non-micro bricks come back)
+    // (keeping track of which of the remaining bricks are removed, and why, for below:
+    // microbricks of anyone cleared for them, and bricks of the categories someone was cleared for)
+    let cleared_ids = cleared_owners
+        .keys()
+        .map(|&(id, _)| id)
+        .collect::<HashSet<_>>();
+    let rules = config.asset_rules(&header2.brick_assets);
+    let mut index = 0;
+    let mut removed = vec![];
+    bricks.retain(|b| {
+        let id = match b.owner_index {
+            0 => PUBLIC_ID,
+            n => header2.brick_owners[n as usize - 1].id,
+        };
+        let public = clear_public && id == PUBLIC_ID;
+        let keep = public || cleared_ids.contains(&id);
+        if keep {
+            removed.push(
+                removed_as(config, &rules, b, stragglers.contains(&index)).filter(|&category| {
+                    (public && category.is_none()) || cleared_owners.contains_key(&(id, category))
+                }),
+            );
+        }
+        index += 1;
+        keep
+    });
 
-    if !players
-        .iter()
-        .any(|p| p.name.eq_ignore_ascii_case(target.as_str()))
-    {
-        return;
+    // in convert mode, swap microbricks over to the replacement asset rather than dropping them,
+    // keeping everything else about the brick as is
+    if config.convert_instead_of_clear {
+        let replacement = match header2
+            .brick_assets
+            .iter()
+            .position(|a| *a == config.convert_asset)
+        {
+            Some(i) => i,
+            None => {
+                header2.brick_assets.push(config.convert_asset.clone());
+                header2.brick_assets.len() - 1
+            }
+        };
+
+        for (brick, &removed) in bricks.iter_mut().zip(removed.iter()) {
+            if removed == Some(None) {
+                brick.asset_name_index = replacement as u32;
+            }
+        }
     }
 
-    omegga.whisper(target, "<size=\"30\"><color=\"a00\">Microbricks are not allowed on this server!</> Please delete your microbricks or <b>they will be cleared</>.</>");
+    // now keep only bricks that aren't being removed
+    // (converted bricks are kept, unless they're still undersized)
+    // (matching assets again, since converting may have added one)
+    let rules = config.asset_rules(&header2.brick_assets);
+    let mut removed = removed.into_iter();
+    bricks.retain(|b| match removed.next().flatten() {
+        Some(None) => config.brick_severity(&rules, b) <= Some(Severity::Warn),
+        Some(Some(_)) => false,
+        None => true,
+    });
+    quarantine::prune_owners(header2, bricks);
+}
+
+/// An owner whose microbricks a scan is clearing.
+struct ClearedOwner {
+    owner: BrickOwner,
+
+    /// How many microbricks are being cleared.
+    count: u32,
+
+    /// The breakdown of their microbricks by asset.
+    assets: String,
+
+    /// The box around their microbricks.
+    bounds: String,
+
+    /// Whether or not most of their microbricks were invisible.
+    hidden: bool,
+
+    /// Whether or not most of their microbricks were buried inside larger bricks.
+    concealed: bool,
+}
+
+/// An owner's entry in a scan's event, with a count depending on the list it's for.
+fn event_owner(config: &Config, micros: &OwnerMicros, count: u32) -> EventOwner {
+    EventOwner::new(
+        micros.owner.id,
+        &micros.owner.name,
+        count,
+        micros
+            .category
+            .map(|category| config.parsed_categories[category].name.as_str()),
+    )
+}
+
+/// What a scan found and did.
+#[derive(Debug, Clone, Default)]
+struct ScanSummary {
+    /// How many owners were warned about their microbricks, or are pending a clear.
+    flagged: usize,
+
+    /// How many owners had their bricks cleared.
+    cleared: usize,
+
+    /// How many microbricks were removed, including public ones.
+    removed: u32,
+}
+
+/// Check a save for microbricks, warning, timing, and clearing their owners,
+/// then emit what was found and done to the config's `summary-plugins`.
+///
+/// With `only` set, every other owner (and public bricks) is ignored entirely,
+/// so their timers and records are left as they are.
+async fn check_save(
+    omegga: &Omegga,
+    config: &Config,
+    path: PathBuf,
+    only: Option<Uuid>,
+) -> Result<ScanSummary> {
+    let mut event = ScanEvent::new(&path, only);
+    let result = scan_save(omegga, config, path, only, &mut event).await;
+    if result.is_ok() {
+        event::emit(omegga, config, &event).await;
+    }
+    result
+}
+
+/// Scan a save for `check_save`, filling in its event along the way.
+async fn scan_save(
+    omegga: &Omegga,
+    config: &Config,
+    path: PathBuf,
+    only: Option<Uuid>,
+    event: &mut ScanEvent,
+) -> Result<ScanSummary> {
+    // while paused, nothing is warned, timed, or cleared
+    if is_paused(omegga).await? {
+        log(
+            omegga,
+            config,
+            LogLevel::Normal,
+            "Enforcement is paused, skipping save",
+        );
+        return Ok(ScanSummary::default());
+    }
+
+    // in a dry run, everything is scanned and reported to admins, but nothing is enforced
+    let dry_run = is_dry_run(omegga).await?;
+    event.dry_run = dry_run;
+
+    let mut reader = SaveReader::new(File::open(path)?)?;
+    let header1 = reader.read_header1()?;
+    let header2 = reader.read_header2()?;
+
+    let brick_count = header1.brick_count;
+    event.bricks = brick_count;
+    update_stats(omegga, |stats| {
+        stats.scans += 1;
+        stats.last_scan = Some(Utc::now().timestamp() as u64);
+        stats.last_scan_bricks = brick_count as u64;
+    })
+    .await?;
+
+    let players = omegga.get_players().await?;
+
+    // expect there to be no microbricks (or bricks of any category)
+    // (undersized bricks can be of any asset, so the header can't rule them out)
+    if config.min_brick_size == 0
+        && !header2
+            .brick_assets
+            .iter()
+            .any(|asset| config.is_banned_asset(asset) || config.category(asset).is_some())
+    {
+        // there are no microbricks! we can safely stop checking this save,
+        // after dropping everyone's timers since nobody has microbricks anymore
+        log(
+            omegga,
+            config,
+            LogLevel::Debug,
+            "No microbrick assets in the save, skipping the brick scan",
+        );
+        match only {
+            _ if dry_run => {}
+            Some(id) => drop_timers(omegga, config, id, &HashSet::new()).await,
+            None => {
+                check_server_alert(omegga, config, &players, 0, vec![]).await?;
+                sweep_records(omegga, config, &players, &HashSet::new()).await?;
+            }
+        }
+        return Ok(ScanSummary::default());
+    }
+
+    // at this point, we know we have microbricks, so let's scan the save for them
+    // (the preview is kept too, so the saves written from this one can be told apart)
+    let SaveData {
+        header1,
+        header2,
+        preview,
+        bricks,
+        components,
+        ..
+    } = read_rest(reader, header1, header2).await?;
+    log(
+        omegga,
+        config,
+        LogLevel::Debug,
+        format!("Read {} bricks", bricks.len()),
+    );
+
+    let mut micro_owners = HashSet::new();
+    let mut cleared_owners = HashMap::new();
+    let mut exempt_owners = HashSet::new();
+    let mut exempt_bricks = 0;
+
+    // bricks owned by a running minigame are part of the game, not anyone's build
+    let minigames = if config.skip_minigame_owners {
+        minigame_names(omegga).await
+    } else {
+        vec![]
+    };
+
+    let Analysis {
+        owners: micro_counts,
+        categories: category_counts,
+        public,
+        public_positions,
+        minigames: minigame_owners,
+        stragglers,
+    } = analyze_bricks(config, &header2, &bricks, only, &minigames);
+    let public_micros = public.values().sum::<u32>();
+
+    for (name, count) in minigame_owners.iter() {
+        log(
+            omegga,
+            config,
+            LogLevel::Debug,
+            format!(
+                "{} has {} microbricks: minigame owner, skipping",
+                name, count
+            ),
+        );
+    }
+
+    // alert admins if the save as a whole has too many microbricks, whoever owns them
+    let total = public_micros
+        + minigame_owners.values().sum::<u32>()
+        + micro_counts
+            .values()
+            .map(|micros| micros.count)
+            .sum::<u32>();
+    let owners = micro_counts
+        .values()
+        .map(|micros| (micros.owner.name.clone(), micros.count))
+        .collect();
+    // (a dry run leaves them alone, so the next real scan still alerts)
+    if only.is_none() && !dry_run {
+        check_server_alert(omegga, config, &players, total, owners).await?;
+        if !config.enforce_public {
+            check_public_alert(omegga, config, &players, &public_positions).await?;
+        }
+    }
+
+    event.owners = micro_counts
+        .values()
+        .chain(category_counts.values())
+        .map(|micros| event_owner(config, micros, micros.count))
+        .collect();
+
+    // players exempted with `/am exempt` are honored alongside the config
+    let store_exempt = get_exempt(omegga).await?;
+    let permits = get_permits(omegga).await?;
+    let watched = get_watched(omegga).await?;
+    let now = Utc::now().timestamp() as u64;
+
+    for micros in micro_counts
+        .into_values()
+        .chain(category_counts.into_values())
+    {
+        let OwnerMicros {
+            owner,
+            count,
+            severity,
+            ..
+        } = micros;
+        let kind = micros.kind(config);
+        let timer = timer_id(config, owner.id, micros.category);
+        if !dry_run {
+            set_name(omegga, owner.id, &owner.name);
+            set_last_seen(omegga, owner.id);
+        }
+        let assets = asset_breakdown(
+            &config.messages,
+            micros.assets.iter().map(|(&asset, &count)| (asset, count)),
+        );
+        let bounds = locations::describe_bounds(&config.messages, &micros.positions);
+        let debug = |branch: &str| {
+            log(
+                omegga,
+                config,
+                LogLevel::Debug,
+                format!(
+                    "{} ({}) has {} {} ({}): {}",
+                    owner.name, owner.id, count, kind, assets, branch
+                ),
+            )
+        };
+
+        // roles are looked up once per owner, and only if something depends on them
+        let roles = if config.exempt_roles.is_empty() && config.clear_after_overrides.is_empty() {
+            vec![]
+        } else {
+            owner_roles(omegga, &players, owner.id).await?
+        };
+
+        if config.is_exempt(owner)
+            || config.has_exempt_role(&roles)
+            || store_exempt.contains_key(&owner.id)
+        {
+            exempt_owners.insert(owner.id);
+            if micros.category.is_none() {
+                exempt_bricks += count;
+            }
+            debug("exempt, skipping");
+            continue;
+        }
+
+        // players with a permit are left alone until it runs out, timers and all
+        if permits.get(&owner.id).is_some_and(|&expires| now < expires) {
+            debug("permitted, skipping");
+            continue;
+        }
+
+        // watched players get no budget, grace period, or timer: any microbrick is cleared
+        let is_watched = watched.contains_key(&owner.id);
+
+        // owners within the budget (and percentage threshold) are left alone
+        let (threshold, over) = match micros.threshold(config) {
+            Some(crossed) => crossed,
+            None if is_watched => (Threshold::Count, 0),
+            None => {
+                debug("within budget, skipping");
+                continue;
+            }
+        };
+        let reason = threshold.describe(config, &micros, over);
+
+        // remember where their microbricks are, so admins can `/am goto` them
+        // along with how many they had when last flagged, to tell whether they're removing them
+        let previous = if micros.category.is_none() {
+            sight(omegga, &micros, dry_run).await?
+        } else {
+            None
+        };
+
+        // warn-only assets never lead to a clear
+        if severity == Severity::Warn {
+            debug("warn-only assets, warning");
+            event.warned.push(event_owner(config, &micros, count));
+            if dry_run {
+                report_dry_run(omegga, config, &players, "dry-run-warn", &owner.name, over).await;
+                continue;
+            }
+
+            announce(
+                omegga,
+                config.announce_warning,
+                &players,
+                Some(owner.id),
+                render(
+                    &config.messages.warning_only,
+                    &[
+                        ("player", &owner.name),
+                        ("count", &format_count(over as u64)),
+                        ("total", &format_count(count as u64)),
+                        ("reason", &reason),
+                        (
+                            "locations",
+                            &locations::describe(&config.messages, &micros.positions),
+                        ),
+                    ],
+                ),
+            );
+            continue;
+        }
+
+        // hiding microbricks is deliberate, so it gets no new player grace and a shorter timer
+        let hidden = micros.mostly_hidden();
+        let concealed = micros.mostly_concealed();
+        let clear_after = config.clear_after_for(&roles, hidden);
+        if !dry_run {
+            // (kept with their timer, so commands can tell when it runs out)
+            set_hidden(omegga, &timer, hidden).await;
+        }
+
+        // new players are only warned until their grace period is up
+        if config.new_player_grace > 0. && !is_watched && !hidden {
+            // (a dry run doesn't start anyone's grace period, it only pretends to)
+            let first_seen = if dry_run {
+                get_timestamp(omegga, format!("seen:{}", owner.id))
+                    .await?
+                    .unwrap_or(now)
+            } else {
+                record_first_seen(omegga, &owner.id.to_string()).await?
+            };
+            let grace_end = first_seen + (config.new_player_grace * 60.) as u64;
+            let now = Utc::now().timestamp() as u64;
+            if now < grace_end {
+                debug("new player grace, warning");
+                event.warned.push(event_owner(config, &micros, count));
+                if dry_run {
+                    report_dry_run(omegga, config, &players, "dry-run-warn", &owner.name, over)
+                        .await;
+                    continue;
+                }
+
+                let minutes = ((grace_end - now) as f32 / 60. + clear_after).ceil();
+                warn_player(omegga, config, &players, &micros, over, minutes, &reason);
+                continue;
+            }
+        }
+
+        // check if a timestamp has already been set for them
+        let ts = match omegga.store_get(format!("ts:{}", timer)).await? {
+            Some(Value::String(s)) => Some(s.parse::<u64>()?),
+            _ => None,
+        };
+
+        // a running timer means they were flagged by the last scan too, so compare against it
+        // (their timer is dropped as soon as a scan finds them back within the budget)
+        let grew = match (ts, previous) {
+            (Some(_), Some(previous)) => {
+                report_progress(omegga, config, &players, owner, previous, count, dry_run).await;
+                count > previous
+            }
+            _ => false,
+        };
+        let escalate = grew && config.escalate_on_growth;
+
+        // if the timer has expired (or there is no grace period at all), clear bricks
+        // otherwise, warn the player
+        let now = Utc::now().timestamp() as u64;
+        let clear_at = ts.unwrap_or(now) + (clear_after * 60.) as u64;
+        if severity == Severity::InstantClear || is_watched || escalate || now >= clear_at {
+            // hold off on clearing until enough consecutive scans have seen their microbricks
+            if !dry_run && !confirm_clear(omegga, config, &timer, now).await? {
+                micro_owners.insert(timer);
+                let message = config.messages.text(
+                    "admin-pending-clear",
+                    &[
+                        ("player", &owner.name),
+                        ("assets", &assets),
+                        ("bounds", &bounds),
+                    ],
+                );
+                debug("pending confirmation, waiting");
+                event.warned.push(event_owner(config, &micros, count));
+                log(omegga, config, LogLevel::Normal, message.clone());
+                notify_admins(omegga, config, &players, message).await;
+                continue;
+            }
+
+            debug("timer expired, clearing");
+            cleared_owners.insert(
+                (owner.id, micros.category),
+                ClearedOwner {
+                    owner: owner.clone(),
+                    count,
+                    assets,
+                    bounds,
+                    hidden,
+                    concealed,
+                },
+            );
+        } else {
+            debug("timer running, warning");
+            event.warned.push(event_owner(config, &micros, count));
+            micro_owners.insert(timer.clone());
+            if dry_run {
+                report_dry_run(omegga, config, &players, "dry-run-warn", &owner.name, over).await;
+                continue;
+            }
+
+            // only the warning that starts their timer is logged, not every one after it
+            if ts.is_none() {
+                omegga.store_set(format!("ts:{}", timer), Value::String(now.to_string()));
+                log_action(omegga, Action::now(ActionKind::Warn, &owner.name, over)).await?;
+
+                let message = config.messages.text(
+                    if micros.category.is_some() {
+                        "admin-flagged-category"
+                    } else if hidden {
+                        "admin-flagged-hidden"
+                    } else if concealed {
+                        "admin-flagged-concealed"
+                    } else {
+                        "admin-flagged"
+                    },
+                    &[
+                        ("player", &owner.name),
+                        ("count", &count),
+                        ("assets", &assets),
+                        ("hidden", &micros.hidden),
+                        ("concealed", &micros.concealed),
+                        ("category", &kind),
+                        ("bounds", &bounds),
+                    ],
+                );
+                log(omegga, config, LogLevel::Normal, message.clone());
+                notify_admins(omegga, config, &players, message).await;
+            }
+
+            let minutes = ((clear_at - now) as f32 / 60.).ceil();
+            warn_player(omegga, config, &players, &micros, over, minutes, &reason);
+        }
+    }
+
+    if exempt_bricks > 0 {
+        log(
+            omegga,
+            config,
+            LogLevel::Normal,
+            format!(
+                "Skipped {} microbricks owned by {} exempt players",
+                exempt_bricks,
+                exempt_owners.len()
+            ),
+        );
+    }
+
+    // public bricks have no owner to warn or ban, so they are cleared right away
+    let clear_public = config.enforce_public && public_micros > 0;
+
+    // the save being checked can be minutes old, and clearing an owner wipes everything
+    // they have right now, so whatever is put back has to come from a save taken just now
+    let (header1, mut header2, preview, mut bricks, components, stragglers) =
+        if !dry_run && (clear_public || !cleared_owners.is_empty()) {
+            let save = match fresh_save(omegga, config).await {
+                Ok(save) => save,
+                Err(e) => bail!(
+                    "couldn't take a fresh save to clear from, nothing was cleared: {}",
+                    e
+                ),
+            };
+            let rules = config.asset_rules(&save.header2.brick_assets);
+            let stragglers = find_stragglers(config, &rules, &save.bricks);
+            (
+                save.header1,
+                save.header2,
+                save.preview,
+                save.bricks,
+                save.components,
+                stragglers,
+            )
+        } else {
+            (header1, header2, preview, bricks, components, stragglers)
+        };
+    let summary = ScanSummary {
+        flagged: micro_owners.len(),
+        cleared: cleared_owners.len(),
+        removed: cleared_owners
+            .values()
+            .map(|cleared| cleared.count)
+            .sum::<u32>()
+            + if clear_public { public_micros } else { 0 },
+    };
+    if clear_public && dry_run {
+        report_dry_run(
+            omegga,
+            config,
+            &players,
+            "dry-run-public",
+            "",
+            public_micros,
+        )
+        .await;
+    } else if clear_public {
+        announce(
+            omegga,
+            config.announce_clear,
+            &players,
+            None,
+            render(
+                config
+                    .messages
+                    .public_clear(config.convert_instead_of_clear),
+                &[("count", &public_micros)],
+            ),
+        );
+        omegga.clear_bricks(PUBLIC_ID.to_string(), true);
+    }
+
+    event.cleared = cleared_owners
+        .iter()
+        .map(|(&(id, category), cleared)| {
+            EventOwner::new(
+                id,
+                &cleared.owner.name,
+                cleared.count,
+                category.map(|category| config.parsed_categories[category].name.as_str()),
+            )
+        })
+        .collect();
+
+    // clear violator bricks, keeping the microbricks removed from the world
+    // so they can be restored if the clear was a mistake
+    let rules = config.asset_rules(&header2.brick_assets);
+    for ((id, category), cleared) in cleared_owners.iter() {
+        let timer = timer_id(config, *id, *category);
+        let kind = match category {
+            Some(category) => config.parsed_categories[*category].name.as_str(),
+            None => "microbricks",
+        };
+        let ClearedOwner {
+            owner,
+            count,
+            assets,
+            bounds,
+            hidden,
+            concealed,
+        } = cleared;
+        if dry_run {
+            let violations = active_violations(
+                &get_violations(omegga, id).await?,
+                config.violation_expiry_days,
+            ) + config.violations_for(*count);
+            report_dry_run(
+                omegga,
+                config,
+                &players,
+                "dry-run-clear",
+                &owner.name,
+                *count,
+            )
+            .await;
+            if violations > config.max_violations && config.enable_bans {
+                event.bans.push(EventOwner::new(
+                    *id,
+                    &owner.name,
+                    violations,
+                    category.map(|_| kind),
+                ));
+                report_dry_run(
+                    omegga,
+                    config,
+                    &players,
+                    "dry-run-ban",
+                    &owner.name,
+                    violations,
+                )
+                .await;
+            }
+            continue;
+        }
+
+        let template = match category {
+            Some(category) => config.category_message(*category, "clear"),
+            None => config.messages.clear(config.convert_instead_of_clear),
+        };
+        announce(
+            omegga,
+            config.announce_clear,
+            &players,
+            Some(*id),
+            render(
+                template,
+                &[
+                    ("player", &owner.name),
+                    ("category", &kind),
+                    ("count", &format_count(*count as u64)),
+                ],
+            ),
+        );
+
+        // converted microbricks stay in the world, but category bricks are always removed
+        let save = if config.convert_instead_of_clear && category.is_none() {
+            None
+        } else {
+            let removed = bricks
+                .iter()
+                .enumerate()
+                .filter(|&(i, b)| {
+                    b.owner_index > 0
+                        && header2.brick_owners[b.owner_index as usize - 1].id == *id
+                        && removed_as(config, &rules, b, stragglers.contains(&i)) == Some(*category)
+                })
+                .map(|(_, b)| b.clone())
+                .collect();
+
+            let snapshot = quarantine::snapshot(&header1, &header2, &preview, &components, removed);
+            match quarantine::write(config, *id, kind, snapshot).await {
+                Ok(name) => Some(name),
+                Err(e) => {
+                    omegga.error(format!("failed to quarantine bricks of {}: {}", id, e));
+                    None
+                }
+            }
+        };
+
+        omegga.clear_bricks(id.to_string(), true);
+        log_action(omegga, Action::now(ActionKind::Clear, &owner.name, *count)).await?;
+        let key = match (hidden, concealed) {
+            (true, _) => Some("admin-cleared-hidden"),
+            (_, true) => Some("admin-cleared-concealed"),
+            _ => None,
+        };
+        if let Some(key) = key {
+            let message = config
+                .messages
+                .text(key, &[("player", &owner.name), ("count", count)]);
+            log(omegga, config, LogLevel::Normal, message.clone());
+            notify_admins(omegga, config, &players, message).await;
+        }
+
+        // every clear goes on their history, even when no violation is added for it
+        let mut history = get_history(omegga, id).await?;
+        history.push(Clear {
+            hidden: *hidden,
+            concealed: *concealed,
+            category: category.map(|_| kind.to_string()),
+            ..Clear::now(*count, save.clone())
+        });
+        set_history(omegga, id, &history);
+
+        let recent = RecentClear {
+            owner: id.to_string(),
+            name: owner.name.clone(),
+            time: Utc::now().timestamp() as u64,
+            save: save.clone(),
+            added: 0,
+            banned: false,
+        };
+
+        // within the cooldown of their last clear, a player's bricks are still cleared,
+        // but they aren't punished again (the save may have been stale)
+        // watched players are punished for every clear
+        let now = Utc::now().timestamp() as u64;
+        let is_watched = watched.contains_key(id);
+        let last_clear_key = format!("lastclear:{}", timer);
+        if let Some(Value::String(s)) = omegga.store_get(last_clear_key.clone()).await? {
+            let last_clear: u64 = s.parse()?;
+            if now < last_clear + (config.clear_cooldown * 60.) as u64 && !is_watched {
+                log(
+                    omegga,
+                    config,
+                    LogLevel::Normal,
+                    format!(
+                        "Clearing {} {} of {} (within clear cooldown, no violation added)",
+                        count, kind, id
+                    ),
+                );
+                update_recent_clears(omegga, |clears| clears.push(recent)).await?;
+                continue;
+            }
+        }
+        omegga.store_set(last_clear_key, Value::String(now.to_string()));
+
+        let added = match config.violations_for(*count) {
+            0 if is_watched => 1,
+            added => added,
+        };
+        let mut record = get_violations(omegga, id).await?;
+        record.extend((0..added).map(|_| Violation {
+            hidden: *hidden,
+            concealed: *concealed,
+            category: category.map(|_| kind.to_string()),
+            save: save.clone(),
+            ..Violation::now()
+        }));
+        set_violations(omegga, id, &record);
+        update_recent_clears(omegga, |clears| {
+            clears.push(RecentClear { added, ..recent })
+        })
+        .await?;
+
+        // only violations within the expiry window count towards bans
+        let violations = active_violations(&record, config.violation_expiry_days);
+
+        log(
+            omegga,
+            config,
+            LogLevel::Normal,
+            format!(
+                "Clearing {} {} of {} ({} violations, {} added): {}, spanning {}",
+                count, kind, id, violations, added, assets, bounds
+            ),
+        );
+
+        if violations > config.max_violations && !config.enable_bans {
+            let message = config.messages.text(
+                "admin-bans-disabled",
+                &[("player", &owner.name), ("violations", &violations)],
+            );
+            log(omegga, config, LogLevel::Normal, message.clone());
+            notify_admins(omegga, config, &players, message).await;
+        }
+
+        if violations > config.max_violations && config.enable_bans && config.kick_first {
+            // the first time a player goes over max violations, they are only kicked
+            let key = format!("kicks:{}", id);
+            let kicks = get_count(omegga, key.clone()).await?;
+
+            if kicks == 0 {
+                omegga.store_set(key, 1.into());
+                log_action(
+                    omegga,
+                    Action::now(ActionKind::Kick, &owner.name, violations),
+                )
+                .await?;
+                log(
+                    omegga,
+                    config,
+                    LogLevel::Normal,
+                    format!("Kicking {} ({} violations)", owner.name, violations),
+                );
+
+                let reason = render(&config.messages.kick_reason, &[("violations", &violations)]);
+                omegga.writeln(format!(
+                    "Chat.Command /Kick \"{}\" \"{}\"",
+                    escape_command_arg(&owner.name),
+                    escape_command_arg(&reason),
+                ));
+                continue;
+            }
+        }
+
+        if violations > config.max_violations && config.enable_bans {
+            // we've hit max violations: start banning the user
+            let key = format!("bans:{}", id);
+            let mut bans = get_count(omegga, key.clone()).await?;
+            bans += 1;
+
+            omegga.store_set(key, bans.into());
+
+            // a negative duration is a permanent ban
+            let permanent = bans as u32 > config.max_bans;
+            let (duration, remaining) = if permanent {
+                (-1., 0)
+            } else {
+                (
+                    config.ban_duration(bans as u32),
+                    config.max_bans - bans as u32,
+                )
+            };
+
+            let reason = render(
+                config.messages.ban_reason(permanent),
+                &[
+                    ("violations", &violations),
+                    ("bans", &bans),
+                    ("remaining", &remaining),
+                    ("duration", &duration_text(&config.messages, duration)),
+                ],
+            );
+
+            announce(
+                omegga,
+                config.announce_ban,
+                &players,
+                Some(*id),
+                render(
+                    &config.messages.ban,
+                    &[
+                        ("player", &owner.name),
+                        ("duration", &duration_text(&config.messages, duration)),
+                    ],
+                ),
+            );
+
+            update_stats(omegga, |stats| stats.bans += 1).await?;
+            event.bans.push(EventOwner::new(
+                *id,
+                &owner.name,
+                violations,
+                category.map(|_| kind),
+            ));
+            log_action(
+                omegga,
+                Action::now(ActionKind::Ban, &owner.name, violations),
+            )
+            .await?;
+            update_recent_clears(omegga, |clears| {
+                if let Some(clear) = clears.last_mut() {
+                    clear.banned = true;
+                }
+            })
+            .await?;
+            omegga.writeln(format!(
+                "Chat.Command /Ban {} {} \"{}\"",
+                id,
+                duration,
+                escape_command_arg(&reason),
+            ));
+        } else {
+            omegga.whisper(
+                id.to_string(),
+                render(
+                    config.messages.violations(config.enable_bans),
+                    &[
+                        ("player", &owner.name),
+                        ("violations", &violations),
+                        ("added", &added),
+                        ("max", &config.max_violations),
+                    ],
+                ),
+            );
+        }
+    }
+
+    // a dry run stops short of touching the world or the records of who wasn't found
+    if dry_run {
+        return Ok(summary);
+    }
+
+    // now, we should have a list of users whose bricks are cleared
+    filter_cleared(
+        config,
+        &mut header2,
+        &mut bricks,
+        &cleared_owners,
+        clear_public,
+        &stragglers,
+    );
+
+    // now we've filtered out the bricks, so we can load everything back in as is
+    // (unless nothing is left to put back, which the game would only complain about)
+    if bricks.is_empty() {
+        if !cleared_owners.is_empty() || clear_public {
+            log(
+                omegga,
+                config,
+                LogLevel::Normal,
+                format!(
+                    "Nothing to restore for the {} cleared owners, skipping the reload",
+                    cleared_owners.len() + clear_public as usize
+                ),
+            );
+        }
+    } else {
+        let save_data = SaveData {
+            header1,
+            header2,
+            preview,
+            bricks,
+            components,
+            ..Default::default()
+        };
+        reload(omegga, config, save_data).await?;
+    }
+
+    // at this point drop the records of users that were not found in this scan
+    micro_owners.extend(
+        exempt_owners
+            .into_iter()
+            .flat_map(|id| timer_ids(config, id)),
+    );
+    match only {
+        Some(id) => drop_timers(omegga, config, id, &micro_owners).await,
+        None => sweep_records(omegga, config, &players, &micro_owners).await?,
+    }
+
+    update_stats(omegga, |stats| {
+        stats.clears += summary.cleared as u64;
+        stats.removed += summary.removed as u64;
+    })
+    .await?;
+
+    Ok(summary)
+}
+
+/// Drop timers and pending clears not in `keep`, along with expired clear cooldowns and permits.
+///
+/// Timers are kept by `timer_id`, so each of an owner's categories is swept on its own.
+async fn sweep_records(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    keep: &HashSet<String>,
+) -> Result<()> {
+    let keys = omegga.store_keys().await?;
+    for key in keys.iter() {
+        let id = match key
+            .strip_prefix("ts:")
+            .or_else(|| key.strip_prefix("pending:"))
+            .or_else(|| key.strip_prefix("hidden:"))
+        {
+            Some(id) => id,
+            None => continue,
+        };
+
+        // if we didn't pick them up, get em outta here
+        if !keep.contains(id) {
+            omegga.store_delete(key.clone()).await;
+        }
+    }
+
+    // and drop any clear cooldowns that have run out
+    let now = Utc::now().timestamp() as u64;
+    for key in keys.iter().filter(|key| key.starts_with("lastclear:")) {
+        if let Some(Value::String(s)) = omegga.store_get(key.clone()).await? {
+            if now >= s.parse::<u64>()? + (config.clear_cooldown * 60.) as u64 {
+                omegga.store_delete(key.clone()).await;
+            }
+        }
+    }
+
+    // and any permits that have run out, telling their owner now or when they next join
+    for (id, expires) in get_permits(omegga).await? {
+        if now < expires {
+            continue;
+        }
+
+        omegga.store_delete(format!("permit:{}", id)).await;
+        let id = id.to_string();
+        match players.iter().find(|p| p.id == id) {
+            Some(p) => omegga.whisper(p.name.clone(), config.messages.get("permit-expired")),
+            None => omegga.store_set(format!("permitnotice:{}", id), Value::Bool(true)),
+        }
+    }
+
+    Ok(())
+}
+
+/// Record where an owner's microbricks are and how many they have, so admins can `/am goto` them,
+/// returning how many they had when last flagged.
+///
+/// In a dry run, nothing is recorded.
+async fn sight(omegga: &Omegga, micros: &OwnerMicros<'_>, dry_run: bool) -> Result<Option<u32>> {
+    let clusters = locations::cluster(&micros.positions)
+        .iter()
+        .map(|c| {
+            let (x, y, z) = c.center();
+            Spot {
+                x,
+                y,
+                z,
+                count: c.count,
+                bounds: Some(c.bounds()),
+            }
+        })
+        .collect();
+
+    let previous = get_sighting(omegga, micros.owner.id)
+        .await?
+        .map(|sighting| sighting.count)
+        .filter(|&previous| previous > 0);
+    if dry_run {
+        return Ok(previous);
+    }
+
+    set_sighting(
+        omegga,
+        micros.owner.id,
+        &Sighting {
+            time: Utc::now().timestamp() as u64,
+            count: micros.count,
+            clusters,
+            assets: micros
+                .assets
+                .iter()
+                .map(|(asset, count)| (asset.to_string(), *count))
+                .collect(),
+        },
+    );
+
+    Ok(previous)
+}
+
+/// Log and tell admins what a dry run would have done, such as `dry-run-clear`.
+async fn report_dry_run(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    key: &str,
+    name: &str,
+    count: u32,
+) {
+    let message = config
+        .messages
+        .text(key, &[("player", &name), ("count", &count)]);
+    log(omegga, config, LogLevel::Normal, message.clone());
+    notify_admins(omegga, config, players, message).await;
+}
+
+/// Log how an owner's microbricks changed since the last scan, telling them how they're doing,
+/// and telling admins if they placed more after being warned.
+async fn report_progress(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    owner: &BrickOwner,
+    previous: u32,
+    count: u32,
+    dry_run: bool,
+) {
+    if count == previous {
+        log(
+            omegga,
+            config,
+            LogLevel::Debug,
+            format!("{}'s microbricks stayed at {}", owner.name, count),
+        );
+        return;
+    }
+
+    let change = if count < previous { "shrank" } else { "grew" };
+    log(
+        omegga,
+        config,
+        LogLevel::Normal,
+        format!(
+            "{}'s microbricks {} from {} to {}",
+            owner.name, change, previous, count
+        ),
+    );
+    if dry_run {
+        return;
+    }
+
+    // the placeholders aren't Send, so everything is rendered before notifying admins
+    let (progress, grew) = {
+        let vars: &[(&str, &dyn Display)] = &[
+            ("player", &owner.name),
+            ("previous", &format_count(previous as u64)),
+            ("count", &format_count(count as u64)),
+            ("difference", &format_count(count.abs_diff(previous) as u64)),
+        ];
+        (
+            config.messages.text(&format!("progress-{}", change), vars),
+            config.messages.text("admin-grew", vars),
+        )
+    };
+    omegga.whisper(owner.id.to_string(), progress);
+
+    if count > previous {
+        log(omegga, config, LogLevel::Normal, grew.clone());
+        notify_admins(omegga, config, players, grew).await;
+    }
+}
+
+/// The records kept by timer id that go with a running timer: the timer itself,
+/// its pending clear, and whether the owner's bricks were hidden.
+const TIMER_PREFIXES: [&str; 3] = ["ts", "pending", "hidden"];
+
+/// What an owner's timer, pending clear, and clear cooldown are keyed by:
+/// their id for microbricks, or `category:id` for one of the categories, like `ts:lights:{id}`.
+fn timer_id(config: &Config, id: impl Display, category: Option<usize>) -> String {
+    match category {
+        Some(category) => format!("{}:{}", config.parsed_categories[category].name, id),
+        None => id.to_string(),
+    }
+}
+
+/// Every timer id an owner could have, for microbricks and each category.
+fn timer_ids(config: &Config, id: impl Display) -> impl Iterator<Item = String> + '_ {
+    let id = id.to_string();
+    std::iter::once(None)
+        .chain((0..config.parsed_categories.len()).map(Some))
+        .map(move |category| timer_id(config, &id, category))
+}
+
+/// Drop a single owner's timers and pending clears, except those in `keep`.
+async fn drop_timers(omegga: &Omegga, config: &Config, id: Uuid, keep: &HashSet<String>) {
+    for timer in timer_ids(config, id).filter(|timer| !keep.contains(timer)) {
+        for prefix in TIMER_PREFIXES {
+            omegga.store_delete(format!("{}:{}", prefix, timer)).await;
+        }
+    }
+}
+
+/// Every timer an owner has running, for microbricks (`None`) and each category,
+/// with when it runs out.
+async fn timer_deadlines(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    id: &str,
+) -> Result<Vec<(Option<usize>, u64)>> {
+    let mut roles = None;
+    let mut deadlines = vec![];
+    for category in std::iter::once(None).chain((0..config.parsed_categories.len()).map(Some)) {
+        let timer = timer_id(config, id, category);
+        let ts = match get_timestamp(omegga, format!("ts:{}", timer)).await? {
+            Some(ts) => ts,
+            None => continue,
+        };
+
+        // roles are only looked up once, and only for owners with a timer
+        if roles.is_none() {
+            roles = Some(match id.parse() {
+                Ok(id) => owner_roles(omegga, players, id).await?,
+                Err(_) => vec![],
+            });
+        }
+        let roles = roles.as_deref().unwrap_or_default();
+        deadlines.push((
+            category,
+            clear_deadline(omegga, config, &timer, ts, roles).await?,
+        ));
+    }
+    Ok(deadlines)
+}
+
+/// When an owner's timer that started at `ts` runs out, as a unix timestamp.
+///
+/// Worked out the same way a scan does, from their roles and whether the scan
+/// that last flagged them found their bricks mostly hidden.
+async fn clear_deadline(
+    omegga: &Omegga,
+    config: &Config,
+    timer: &str,
+    ts: u64,
+    roles: &[String],
+) -> Result<u64> {
+    let hidden = is_hidden(omegga, timer).await?;
+    Ok(ts + (config.clear_after_for(roles, hidden) * 60.) as u64)
+}
+
+/// What a brick is removed as when its owner is cleared: `Some(None)` for a microbrick,
+/// `Some(Some(category))` for a brick of one of the categories, or `None` if it's never removed.
+///
+/// Warn-only microbricks and stragglers are never removed.
+fn removed_as(
+    config: &Config,
+    rules: &AssetRules,
+    brick: &Brick,
+    straggler: bool,
+) -> Option<Option<usize>> {
+    match config.brick_severity(rules, brick) {
+        Some(severity) if severity > Severity::Warn && !straggler => Some(None),
+        Some(_) => None,
+        None => config.brick_category(rules, brick).map(Some),
+    }
+}
+
+/// Record another scan that found an owner's microbricks after their timer expired,
+/// returning whether or not enough consecutive scans have now seen them to clear.
+///
+/// Pending clears are keyed by `timer_id`.
+async fn confirm_clear(omegga: &Omegga, config: &Config, timer: &str, now: u64) -> Result<bool> {
+    if config.confirmation_scans <= 1 {
+        return Ok(true);
+    }
+
+    // a pending clear that hasn't been followed up on in a while starts over
+    let scans = match get_pending(omegga, timer).await? {
+        Some(pending) if now < pending.time + PENDING_EXPIRY_MINUTES * 60 => pending.scans + 1,
+        _ => 1,
+    };
+
+    if scans >= config.confirmation_scans {
+        omegga.store_delete(format!("pending:{}", timer)).await;
+        return Ok(true);
+    }
+
+    set_pending(omegga, timer, &Pending { scans, time: now });
+    Ok(false)
+}
+
+/// Get the names of the server's running minigames, which own the bricks they spawn.
+///
+/// If the minigames can't be fetched, no bricks are treated as minigame bricks.
+async fn minigame_names(omegga: &Omegga) -> Vec<String> {
+    let minigames = match omegga.request("getMinigames", None).await {
+        Ok(Some(Value::Array(minigames))) => minigames,
+        _ => return vec![],
+    };
+
+    minigames
+        .iter()
+        .flat_map(|m| [m.get("name"), m.get("ruleset")])
+        .filter_map(|v| v.and_then(Value::as_str).map(String::from))
+        .collect()
+}
+
+/// Get an owner's roles.
+///
+/// Only online players can be looked up, so offline owners have no roles.
+async fn owner_roles(omegga: &Omegga, players: &[Player], id: Uuid) -> Result<Vec<String>> {
+    let id = id.to_string();
+    if !players.iter().any(|p| p.id == id) {
+        return Ok(vec![]);
+    }
+
+    Ok(omegga.get_player_roles(id).await?.unwrap_or_default())
+}
+
+/// Summarize microbricks by asset, most common first, like `PB_DefaultMicroBrick ×412`.
+///
+/// Only the first few assets are named, so owners with many kinds don't flood chat.
+fn asset_breakdown<'a>(
+    messages: &Messages,
+    assets: impl IntoIterator<Item = (&'a str, u32)>,
+) -> String {
+    let mut assets = assets.into_iter().collect::<Vec<_>>();
+    assets.sort_by_key(|&(asset, count)| (std::cmp::Reverse(count), asset));
+
+    let mut parts = assets
+        .iter()
+        .take(ASSET_BREAKDOWN_LIMIT)
+        .map(|(asset, count)| format!("{} ×{}", asset, count))
+        .collect::<Vec<_>>();
+    if assets.len() > ASSET_BREAKDOWN_LIMIT {
+        parts.push(messages.text(
+            "asset-breakdown-more",
+            &[("count", &(assets.len() - ASSET_BREAKDOWN_LIMIT))],
+        ));
+    }
+    parts.join(", ")
+}
+
+/// Format a count with thousands separators, like `3,212`.
+fn format_count(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Describe a ban duration in minutes, where a negative duration is permanent.
+fn duration_text(messages: &Messages, minutes: f32) -> String {
+    if minutes < 0. {
+        messages.get("duration-permanent").into()
+    } else if minutes >= 60. * 24. {
+        messages.text("duration-days", &[("n", &(minutes / 60. / 24.))])
+    } else if minutes >= 60. {
+        messages.text("duration-hours", &[("n", &(minutes / 60.))])
+    } else {
+        messages.text("duration-minutes", &[("n", &minutes)])
+    }
+}
+
+/// Get the time a player was first seen, recording it as now if they haven't been seen before.
+async fn record_first_seen(omegga: &Omegga, id: &str) -> Result<u64> {
+    let key = format!("seen:{}", id);
+    match omegga.store_get(key.clone()).await? {
+        Some(Value::String(s)) => Ok(s.parse()?),
+        _ => {
+            let now = Utc::now().timestamp() as u64;
+            omegga.store_set(key, Value::String(now.to_string()));
+            Ok(now)
+        }
+    }
+}
+
+/// Notify admins once when the server-wide microbrick total crosses `server-micro-alert`,
+/// re-arming only once the total drops back below it.
+async fn check_server_alert(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    total: u32,
+    mut owners: Vec<(String, u32)>,
+) -> Result<()> {
+    if config.server_micro_alert == 0 {
+        return Ok(());
+    }
+
+    let alerted = matches!(
+        omegga.store_get("server-alert").await?,
+        Some(Value::Bool(true))
+    );
+
+    if total < config.server_micro_alert {
+        if alerted {
+            omegga.store_delete("server-alert").await;
+        }
+        return Ok(());
+    }
+
+    if alerted {
+        return Ok(());
+    }
+
+    owners.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let top = owners
+        .iter()
+        .take(SERVER_ALERT_TOP_OWNERS)
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let top = if top.is_empty() {
+        config.messages.get("admin-server-alert-no-owners")
+    } else {
+        top.as_str()
+    };
+    let message = config.messages.text(
+        "admin-server-alert",
+        &[
+            ("total", &total),
+            ("threshold", &config.server_micro_alert),
+            ("owners", &top),
+        ],
+    );
+    log(omegga, config, LogLevel::Normal, message.clone());
+    notify_admins(omegga, config, players, message).await;
+    omegga.store_set("server-alert", Value::Bool(true));
+    Ok(())
+}
+
+/// Notify admins when there are more public microbricks than `public-micro-alert`,
+/// which are otherwise left alone, at most once every `PUBLIC_ALERT_INTERVAL`.
+async fn check_public_alert(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    positions: &[(i32, i32, i32)],
+) -> Result<()> {
+    let count = positions.len() as u32;
+    if config.public_micro_alert == 0 || count <= config.public_micro_alert {
+        return Ok(());
+    }
+
+    let now = Utc::now().timestamp() as u64;
+    if let Some(Value::String(s)) = omegga.store_get("public-alert").await? {
+        if now < s.parse::<u64>()? + PUBLIC_ALERT_INTERVAL {
+            return Ok(());
+        }
+    }
+
+    let message = config.messages.text(
+        "admin-public-alert",
+        &[
+            ("count", &count),
+            ("threshold", &config.public_micro_alert),
+            (
+                "locations",
+                &locations::describe(&config.messages, positions),
+            ),
+        ],
+    );
+    log(omegga, config, LogLevel::Normal, message.clone());
+    notify_admins(omegga, config, players, message).await;
+    omegga.store_set("public-alert", Value::String(now.to_string()));
+    Ok(())
+}
+
+/// Have the game write a fresh save named after `name`, returning its path once it's on disk.
+///
+/// Each save gets its own name, so saves taken at the same time can't clobber each other.
+async fn save_now(omegga: &Omegga, config: &Config, name: &str) -> Result<PathBuf> {
+    let name = format!("{}_{}", name, Utc::now().timestamp_millis());
+    let path = Path::new(&config.saves_dir).join(format!("{}.brs", name));
+
+    // get rid of any stale save so we know when the new one shows up
+    let _ = fs::remove_file(&path);
+    omegga.save_bricks(name.as_str()).await?;
+
+    // the game writes large saves over a while, so the save is only done
+    // once its size stops changing (the wait starts over whenever it grows)
+    let (mut size, mut stable, mut waited) = (None, 0, Duration::ZERO);
+    loop {
+        tokio::time::sleep(SAVE_POLL_INTERVAL).await;
+        waited += SAVE_POLL_INTERVAL;
+
+        let current = fs::metadata(&path).ok().map(|metadata| metadata.len());
+        if current.is_some_and(|len| len > 0) && current == size {
+            stable += 1;
+            if stable >= SAVE_STABLE_POLLS {
+                return Ok(path);
+            }
+        } else if current != size {
+            (size, stable, waited) = (current, 0, Duration::ZERO);
+        }
+
+        if waited >= SAVE_TIMEOUT {
+            bail!("timed out waiting for the game to save");
+        }
+    }
+}
+
+/// Write a check's filtered save and load it into the game.
+async fn reload(omegga: &Omegga, config: &Config, save_data: SaveData) -> Result<()> {
+    // each check writes its own save, so overlapping checks can't load each other's
+    let temp_save = format!(
+        "{}_{}.brs",
+        config.temp_save_stem(),
+        Utc::now().timestamp_millis()
+    );
+    let dir = Path::new(&config.saves_dir).join(TEMP_DIR);
+    let temp_path = dir.join(&temp_save);
+    let path = temp_path.clone();
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        fs::create_dir_all(&dir)?;
+        SaveWriter::new(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?,
+            save_data,
+        )
+        .write()?;
+        Ok(())
+    })
+    .await??;
+
+    // artificial delay: we are literally too fast for brickadia
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // load it into the game
+    omegga
+        .load_bricks(
+            format!("{}/{}", TEMP_DIR, temp_save),
+            config.reload_quiet,
+            config.reload_offset(),
+        )
+        .await?;
+    let _ = fs::remove_file(&temp_path);
+    Ok(())
+}
+
+/// Read a whole save.
+///
+/// Parsing every brick takes a while, so it's done on a blocking thread, off the async runtime.
+async fn read_save(path: &Path) -> Result<SaveData> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || Ok(SaveReader::new(File::open(path)?)?.read_all()?)).await?
+}
+
+/// Read the rest of a save whose headers have already been read, on a blocking thread.
+async fn read_rest(
+    mut reader: SaveReader<File>,
+    header1: Header1,
+    header2: Header2,
+) -> Result<SaveData> {
+    tokio::task::spawn_blocking(move || {
+        let preview = reader.read_preview()?;
+        let (bricks, components) = reader.read_bricks(&header1, &header2)?;
+        Ok(SaveData {
+            header1,
+            header2,
+            preview,
+            bricks,
+            components,
+            ..Default::default()
+        })
+    })
+    .await?
+}
+
+/// Save the world right now and read it back, to clear from.
+async fn fresh_save(omegga: &Omegga, config: &Config) -> Result<SaveData> {
+    let path = save_now(omegga, config, CLEAR_SAVE_NAME).await?;
+    let save = read_save(&path).await;
+    let _ = fs::remove_file(&path);
+    save
+}
+
+/// Log a message to the console if the config's `log-level` allows it.
+///
+/// Errors should go straight to `omegga.error`, which is never suppressed.
+fn log(omegga: &Omegga, config: &Config, level: LogLevel, message: impl Into<String>) {
+    if config.log_level >= level {
+        omegga.log(message.into());
+    }
+}
+
+/// Whisper a notification to every online authorized player,
+/// except those who turned notifications off with `/am notify off`.
+async fn notify_admins(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    message: impl Into<String>,
+) {
+    let message = message.into();
+    for player in players.iter().filter(|p| config.is_authorized(p)) {
+        // if the preference can't be read, err on the side of notifying
+        if !wants_notifications(omegga, &player.id)
+            .await
+            .unwrap_or(true)
+        {
+            continue;
+        }
+        omegga.whisper(player.name.clone(), message.clone());
+    }
+}
+
+fn warn_player(
+    omegga: &Omegga,
+    config: &Config,
+    players: &[Player],
+    micros: &OwnerMicros,
+    over: u32,
+    minutes: f32,
+    reason: &str,
+) {
+    let template = match micros.category {
+        Some(category) => config.category_message(category, "warning"),
+        None => &config.messages.warning,
+    };
+    announce(
+        omegga,
+        config.announce_warning,
+        players,
+        Some(micros.owner.id),
+        render(
+            template,
+            &[
+                ("player", &micros.owner.name),
+                ("category", &micros.kind(config)),
+                ("count", &format_count(over as u64)),
+                ("total", &format_count(micros.count as u64)),
+                ("reason", &reason),
+                ("minutes", &minutes),
+                (
+                    "locations",
+                    &locations::describe(&config.messages, &micros.positions),
+                ),
+            ],
+        ),
+    );
+}
+
+/// Send an enforcement message over the given channel.
+///
+/// Whispers go to `target`, and are dropped if there is no target or they aren't online.
+fn announce(
+    omegga: &Omegga,
+    channel: Channel,
+    players: &[Player],
+    target: Option<Uuid>,
+    message: String,
+) {
+    match channel {
+        Channel::Broadcast => omegga.broadcast(message),
+        Channel::Whisper => {
+            let target = match target {
+                Some(target) => target.to_string(),
+                None => return,
+            };
+
+            if players.iter().any(|p| p.id == target) {
+                omegga.whisper(target, message);
+            }
+        }
+        Channel::None => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::config;
+    use brickadia::{
+        read::SaveReader,
+        save::{Component, Direction, Rotation, UnrealType},
+        write::SaveWriter,
+    };
+    use serde_json::json;
+
+    const DIRECTIONS: [Direction; 6] = [
+        Direction::XPositive,
+        Direction::XNegative,
+        Direction::YPositive,
+        Direction::YNegative,
+        Direction::ZPositive,
+        Direction::ZNegative,
+    ];
+
+    const ROTATIONS: [Rotation; 4] = [
+        Rotation::Deg0,
+        Rotation::Deg90,
+        Rotation::Deg180,
+        Rotation::Deg270,
+    ];
+
+    /// A brick of the given size at `(100, 200, 300)`, facing `direction`.
+    fn brick(size: (u32, u32, u32), direction: &Direction, rotation: &Rotation) -> Brick {
+        Brick {
+            size: Size::Procedural(size.0, size.1, size.2),
+            position: (100, 200, 300),
+            direction: direction.clone(),
+            rotation: rotation.clone(),
+            ..Brick::default()
+        }
+    }
+
+    /// A brick's extent along each axis, from its bounds.
+    fn extents(brick: &Brick) -> (i32, i32, i32) {
+        let ((x1, y1, z1), (x2, y2, z2)) = brick_bounds(brick).unwrap();
+        assert_eq!(
+            (x1 + x2, y1 + y2, z1 + z2),
+            (200, 400, 600),
+            "bounds aren't centered"
+        );
+        ((x2 - x1) / 2, (y2 - y1) / 2, (z2 - z1) / 2)
+    }
+
+    #[test]
+    fn micro_on_its_side() {
+        // a 2x2x1 micro is thin along whichever axis it faces, however it's turned
+        for direction in DIRECTIONS.iter() {
+            for rotation in ROTATIONS.iter() {
+                let brick = brick((2, 2, 1), direction, rotation);
+                let expected = match direction {
+                    Direction::XPositive | Direction::XNegative => (1, 2, 2),
+                    Direction::YPositive | Direction::YNegative => (2, 1, 2),
+                    Direction::ZPositive | Direction::ZNegative => (2, 2, 1),
+                };
+                assert_eq!(extents(&brick), expected, "{:?} {:?}", direction, rotation);
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_follow_rotation() {
+        // a 1x5 plate, 5 studs along its y
+        let plate = (5, 25, 2);
+        let cases = [
+            // lying flat, and turned a quarter
+            (
+                Direction::ZPositive,
+                Rotation::Deg0,
+                ((95, 175, 298), (105, 225, 302)),
+            ),
+            (
+                Direction::ZPositive,
+                Rotation::Deg90,
+                ((75, 195, 298), (125, 205, 302)),
+            ),
+            (
+                Direction::ZNegative,
+                Rotation::Deg180,
+                ((95, 175, 298), (105, 225, 302)),
+            ),
+            // on its side facing x, standing upright once turned a quarter
+            (
+                Direction::XPositive,
+                Rotation::Deg0,
+                ((98, 175, 295), (102, 225, 305)),
+            ),
+            (
+                Direction::XPositive,
+                Rotation::Deg90,
+                ((98, 195, 275), (102, 205, 325)),
+            ),
+            // on its side facing y
+            (
+                Direction::YNegative,
+                Rotation::Deg0,
+                ((75, 198, 295), (125, 202, 305)),
+            ),
+            (
+                Direction::YPositive,
+                Rotation::Deg270,
+                ((95, 198, 275), (105, 202, 325)),
+            ),
+        ];
+        for (direction, rotation, expected) in cases.iter() {
+            let bounds = brick_bounds(&brick(plate, direction, rotation));
+            assert_eq!(bounds, Some(*expected), "{:?} {:?}", direction, rotation);
+        }
+    }
+
+    #[test]
+    fn empty_bricks_have_no_bounds() {
+        assert_eq!(brick_bounds(&Brick::default()), None);
+    }
+
+    /// A brick owned by the `owner`th owner, carrying `component` with its brick's index.
+    fn owned(asset: u32, owner: u32, component: Option<&str>, i: usize) -> Brick {
+        let mut brick = Brick {
+            asset_name_index: asset,
+            owner_index: owner,
+            ..Brick::default()
+        };
+        if let Some(name) = component {
+            let properties = [("Index".to_string(), UnrealType::Float(i as f32))];
+            brick
+                .components
+                .insert(name.into(), properties.iter().cloned().collect());
+        }
+        brick
+    }
+
+    #[test]
+    fn components_follow_filtered_bricks() {
+        let config = config(json!({}));
+        let owners = (1..=2)
+            .map(|n| BrickOwner {
+                name: format!("owner{}", n),
+                id: Uuid::from_u128(n),
+                bricks: 0,
+            })
+            .collect();
+        let mut header2 = Header2 {
+            brick_assets: vec!["PB_DefaultBrick".into(), "PB_DefaultMicroBrick".into()],
+            brick_owners: owners,
+            ..Header2::default()
+        };
+
+        // owner 1 is cleared for their microbricks, owner 2 isn't cleared at all
+        let mut bricks = vec![
+            owned(1, 1, Some("BCD_PointLight"), 0),
+            owned(0, 1, Some("BCD_PointLight"), 1),
+            owned(0, 2, Some("BCD_SpotLight"), 2),
+            owned(1, 1, None, 3),
+            owned(0, 1, Some("BCD_Interact"), 4),
+            owned(0, 1, None, 5),
+        ];
+
+        // the brick indices are the save's originals, which filtering leaves stale
+        let component = |brick_indices: Vec<u32>| Component {
+            version: 1,
+            brick_indices,
+            properties: [("Index".to_string(), "Float".to_string())]
+                .iter()
+                .cloned()
+                .collect(),
+        };
+        let components = [
+            ("BCD_PointLight", component(vec![0, 1])),
+            ("BCD_SpotLight", component(vec![2])),
+            ("BCD_Interact", component(vec![4])),
+        ]
+        .iter()
+        .map(|(name, component)| (name.to_string(), component.clone()))
+        .collect();
+
+        let mut cleared_owners = HashMap::new();
+        cleared_owners.insert(
+            (Uuid::from_u128(1), None),
+            ClearedOwner {
+                owner: header2.brick_owners[0].clone(),
+                count: 2,
+                assets: String::new(),
+                bounds: String::new(),
+                hidden: false,
+                concealed: false,
+            },
+        );
+        filter_cleared(
+            &config,
+            &mut header2,
+            &mut bricks,
+            &cleared_owners,
+            false,
+            &HashSet::new(),
+        );
+        assert_eq!(bricks.len(), 3);
+        assert_eq!(header2.brick_owners.len(), 1);
+        assert_eq!(header2.brick_owners[0].bricks, 3);
+
+        let mut buffer = vec![];
+        let save = SaveData {
+            header2,
+            bricks,
+            components,
+            ..SaveData::default()
+        };
+        SaveWriter::new(&mut buffer, save).write().unwrap();
+        let save = SaveReader::new(buffer.as_slice())
+            .unwrap()
+            .read_all()
+            .unwrap();
+
+        let mut names = save.components.keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["BCD_Interact", "BCD_PointLight"]);
+        assert_eq!(save.components["BCD_PointLight"].brick_indices, [0]);
+        assert_eq!(save.components["BCD_Interact"].brick_indices, [1]);
+
+        // each brick still carries its own component's properties
+        let indices = save
+            .bricks
+            .iter()
+            .map(|brick| {
+                brick
+                    .components
+                    .values()
+                    .next()
+                    .map(|properties| match properties["Index"] {
+                        UnrealType::Float(i) => i as usize,
+                        _ => panic!("wrong property type"),
+                    })
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [Some(1), Some(4), None]);
+    }
+
+    #[test]
+    fn severity_ignores_orientation() {
+        let undersized = config(json!({ "banned-assets": [], "min-brick-size": 2 }));
+        let sized = config(json!({ "banned-assets": [], "min-brick-size": 1 }));
+        let banned = config(json!({ "banned-assets": ["Micro"] }));
+        let assets = vec![
+            "PB_DefaultBrick".to_string(),
+            "PB_DefaultMicroBrick".to_string(),
+        ];
+
+        for direction in DIRECTIONS.iter() {
+            for rotation in ROTATIONS.iter() {
+                let mut brick = brick((2, 2, 1), direction, rotation);
+                let context = format!("{:?} {:?}", direction, rotation);
+
+                let rules = undersized.asset_rules(&assets);
+                assert_eq!(
+                    undersized.brick_severity(&rules, &brick),
+                    Some(Severity::Clear),
+                    "{}",
+                    context
+                );
+
+                let rules = sized.asset_rules(&assets);
+                assert_eq!(sized.brick_severity(&rules, &brick), None, "{}", context);
+
+                let rules = banned.asset_rules(&assets);
+                assert_eq!(banned.brick_severity(&rules, &brick), None, "{}", context);
+                brick.asset_name_index = 1;
+                assert_eq!(
+                    banned.brick_severity(&rules, &brick),
+                    Some(Severity::Clear),
+                    "{}",
+                    context
+                );
+            }
+        }
+    }
 }
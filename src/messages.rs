@@ -0,0 +1,1438 @@
+use std::{collections::HashMap, fmt::Display, fs::File, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Issue;
+
+/// Every player-facing string, keyed by the name it is overridden by in `messages.json`,
+/// with its built-in English text and the placeholders it supports.
+const STRINGS: &[(&str, &str, &[&str])] = &[
+    (
+        "message-warning",
+        "<size=\"30\"><color=\"a00\">Microbricks are not allowed on this server!</> You have <b>{total}</> microbricks, {reason}. Please delete your microbricks or <b>they will be cleared</>.</> They are at: {locations}",
+        &["player", "count", "total", "reason", "minutes", "locations"],
+    ),
+    (
+        "message-warning-only",
+        "<color=\"a00\">Please avoid using microbricks on this server.</> You have <b>{total}</> restricted bricks, {reason}, at: {locations}",
+        &["player", "count", "total", "reason", "locations"],
+    ),
+    (
+        "message-clear",
+        "Clearing <color=\"ff0\">{player}</>'s <b>{count}</> microbricks...",
+        &["player", "count"],
+    ),
+    (
+        "category-warning",
+        "<size=\"30\"><color=\"a00\">Too many {category} bricks!</> You have <b>{total}</>, {reason}. Please delete some or <b>they will be cleared</>.</> They are at: {locations}",
+        &["player", "category", "count", "total", "reason", "minutes", "locations"],
+    ),
+    (
+        "category-clear",
+        "Clearing <color=\"ff0\">{player}</>'s <b>{count}</> {category} bricks...",
+        &["player", "category", "count"],
+    ),
+    (
+        "message-convert",
+        "Converting <color=\"ff0\">{player}</>'s <b>{count}</> microbricks to regular bricks...",
+        &["player", "count"],
+    ),
+    (
+        "message-public-convert",
+        "Converting <b>{count}</> public microbricks to regular bricks...",
+        &["count"],
+    ),
+    (
+        "message-public-clear",
+        "Clearing <b>{count}</> public microbricks...",
+        &["count"],
+    ),
+    (
+        "message-violations",
+        "<b>You received {added} microbrick violations and now have {violations}. After {max}, you will be temporarily banned.</>",
+        &["player", "violations", "added", "max"],
+    ),
+    (
+        "message-violations-no-bans",
+        "<b>You received {added} microbrick violations and now have {violations}.</>",
+        &["player", "violations", "added"],
+    ),
+    (
+        "kick-reason",
+        "Microbricks are not allowed on this server. You will be banned if you keep placing them.",
+        &["violations"],
+    ),
+    (
+        "message-ban",
+        "<color=\"ff0\">{player}</> has been banned for placing microbricks ({duration}).",
+        &["player", "duration"],
+    ),
+    (
+        "ban-reason-temporary",
+        "Microbricks are not allowed on this server. You are banned for {duration}. This ban will be permanent in {remaining} more violations.",
+        &["violations", "bans", "remaining", "duration"],
+    ),
+    (
+        "ban-reason-permanent",
+        "Microbricks are not allowed on this server.",
+        &["violations", "bans", "remaining", "duration"],
+    ),
+    (
+        "location",
+        "{count} around ({x}, {y}, {z})",
+        &["count", "x", "y", "z"],
+    ),
+    ("location-rest", "{count} elsewhere", &["count"]),
+    (
+        "location-bounds",
+        "({x1}, {y1}, {z1}) to ({x2}, {y2}, {z2}), centered around ({x}, {y}, {z})",
+        &["x1", "y1", "z1", "x2", "y2", "z2", "x", "y", "z"],
+    ),
+    ("duration-permanent", "permanent", &[]),
+    ("duration-days", "{n} days", &["n"]),
+    ("duration-hours", "{n} hours", &["n"]),
+    ("duration-minutes", "{n} minutes", &["n"]),
+    (
+        "permit-expired",
+        "Your permit to use microbricks has expired. Microbricks are enforced as usual again.",
+        &[],
+    ),
+    (
+        "dry-run-warn",
+        "[dry run] would warn {player} about {count} microbricks over the limit",
+        &["player", "count"],
+    ),
+    (
+        "dry-run-clear",
+        "[dry run] would clear {player}, removing {count} microbricks",
+        &["player", "count"],
+    ),
+    (
+        "dry-run-ban",
+        "[dry run] would ban {player} at {count} violations",
+        &["player", "count"],
+    ),
+    (
+        "dry-run-public",
+        "[dry run] would clear {count} public microbricks",
+        &["count"],
+    ),
+    (
+        "admin-pending-clear",
+        "{player}'s microbricks ({assets}) are pending a clear, waiting for the next scan to confirm. They span {bounds}",
+        &["player", "assets", "bounds"],
+    ),
+    (
+        "admin-flagged",
+        "{player} was warned about {count} microbricks: {assets}. They span {bounds}",
+        &["player", "count", "assets", "hidden", "concealed", "category", "bounds"],
+    ),
+    (
+        "admin-flagged-hidden",
+        "{player} was warned about {count} microbricks, {hidden} of them <b>invisible</>: {assets}. They span {bounds}",
+        &["player", "count", "assets", "hidden", "concealed", "category", "bounds"],
+    ),
+    (
+        "admin-flagged-category",
+        "{player} was warned about {count} {category} bricks: {assets}. They span {bounds}",
+        &["player", "count", "assets", "hidden", "concealed", "category", "bounds"],
+    ),
+    (
+        "admin-flagged-concealed",
+        "{player} was warned about {count} microbricks, {concealed} of them <b>buried inside other bricks</>: {assets}. They span {bounds}",
+        &["player", "count", "assets", "hidden", "concealed", "category", "bounds"],
+    ),
+    (
+        "admin-cleared-hidden",
+        "{player}'s {count} microbricks were cleared. Most of them were <b>invisible</>, so they got no grace period.",
+        &["player", "count"],
+    ),
+    (
+        "admin-cleared-concealed",
+        "{player}'s {count} microbricks were cleared. Most of them were <b>buried inside other bricks</>.",
+        &["player", "count"],
+    ),
+    ("asset-breakdown-more", "+{count} more", &["count"]),
+    (
+        "threshold-count",
+        "{count} over the limit of {max}",
+        &["count", "max"],
+    ),
+    (
+        "threshold-volume",
+        "{volume} micro-units of them, over the volume limit of {max}",
+        &["count", "volume", "max"],
+    ),
+    (
+        "threshold-percent",
+        "{percent}% of your bricks, over the limit of {max}%",
+        &["count", "percent", "max"],
+    ),
+    (
+        "threshold-density",
+        "{count} of them packed too densely in one spot ({density} per cubic stud, over the density limit of {max})",
+        &["count", "density", "max"],
+    ),
+    (
+        "admin-grew",
+        "{player} placed {difference} more microbricks after being warned, up from {previous} to {count}",
+        &["player", "previous", "count", "difference"],
+    ),
+    (
+        "progress-shrank",
+        "You've removed {difference} of your {previous} microbricks. {count} remain.",
+        &["player", "previous", "count", "difference"],
+    ),
+    (
+        "progress-grew",
+        "<color=\"a00\">You've placed {difference} more microbricks since your warning, for {count} in total.</>",
+        &["player", "previous", "count", "difference"],
+    ),
+    (
+        "admin-bans-disabled",
+        "{player} would have been banned ({violations} violations), but bans are disabled",
+        &["player", "violations"],
+    ),
+    (
+        "admin-server-alert",
+        "The server has <b>{total}</> microbricks, over the alert threshold of {threshold}. Top owners: {owners}",
+        &["total", "threshold", "owners"],
+    ),
+    ("admin-server-alert-no-owners", "none", &[]),
+    (
+        "admin-public-alert",
+        "There are <b>{count}</> public microbricks, over the alert threshold of {threshold}. They aren't cleared automatically, but can be found at: {locations}",
+        &["count", "threshold", "locations"],
+    ),
+    (
+        "command-ambiguous-player",
+        "More than one player matches: {players}. Please be more specific, or use their id.",
+        &["players"],
+    ),
+    (
+        "command-clean-no-player",
+        "Please specify a player to clean, by name or id.",
+        &[],
+    ),
+    (
+        "command-clean-expired-invalid",
+        "<b>{days}</> isn't a valid number of days.",
+        &["days"],
+    ),
+    (
+        "command-clean-expired",
+        "Removed <b>{count}</> records older than {days} days.",
+        &["count", "days"],
+    ),
+    (
+        "command-clean-expired-dry",
+        "<b>{count}</> records are older than {days} days. Nothing was removed.",
+        &["count", "days"],
+    ),
+    (
+        "command-clean",
+        "Cleared <b>{player}</>'s record, if any.",
+        &["player"],
+    ),
+    ("command-export-started", "Exporting every record...", &[]),
+    (
+        "command-export",
+        "Exported <b>{count}</> records to <code>{path}</>.",
+        &["count", "path"],
+    ),
+    (
+        "command-import-missing",
+        "There's no export named <b>{file}</> in the exports directory.",
+        &["file"],
+    ),
+    (
+        "command-import-started",
+        "Importing records from <b>{file}</>...",
+        &["file"],
+    ),
+    (
+        "command-import-invalid",
+        "<color=\"f00\">Nothing was imported:</> {error}",
+        &["error"],
+    ),
+    (
+        "command-import-none",
+        "Nothing was imported, every record was already up to date.",
+        &[],
+    ),
+    (
+        "command-import",
+        "Imported <b>{count}</> records: {records}.",
+        &["count", "records"],
+    ),
+    ("command-wipe", "OK, all records wiped.", &[]),
+    (
+        "command-wipe-disabled",
+        "Wiping all records is disabled on this server.",
+        &[],
+    ),
+    (
+        "command-wipe-confirm",
+        "<b>Are you sure you wish to wipe all records?</> Please run <code>/am wipe confirm {token}</> within {seconds} seconds to confirm.",
+        &["token", "seconds"],
+    ),
+    (
+        "command-wipe-mismatch",
+        "That token doesn't match the one you were given. Please check it and try again.",
+        &[],
+    ),
+    (
+        "command-wipe-expired",
+        "That token has expired. Please run <code>/am wipe</> again for a new one.",
+        &[],
+    ),
+    (
+        "command-wipe-no-token",
+        "You haven't started a wipe. Please run <code>/am wipe</> first.",
+        &[],
+    ),
+    (
+        "command-forgiveall",
+        "OK, forgave <b>{violations}</> violation records and reset <b>{timers}</> timers.",
+        &["violations", "timers"],
+    ),
+    (
+        "command-forgiveall-usage",
+        "Usage: <code>/am forgiveall [timers]</>",
+        &[],
+    ),
+    (
+        "command-forgiveall-confirm",
+        "<b>Are you sure you wish to reset everyone's violations?</> Please run <code>/am {command} confirm {token}</> within {seconds} seconds to confirm.",
+        &["command", "token", "seconds"],
+    ),
+    (
+        "command-forgiveall-mismatch",
+        "That token doesn't match the one you were given. Please check it and try again.",
+        &[],
+    ),
+    (
+        "command-forgiveall-expired",
+        "That token has expired. Please run <code>/am forgiveall</> again for a new one.",
+        &[],
+    ),
+    (
+        "command-forgiveall-no-token",
+        "You haven't started a reset. Please run <code>/am forgiveall</> first, with the same flags.",
+        &[],
+    ),
+    (
+        "command-invalid",
+        "Invalid subcommand <code>/am {subcommand}</>. Run <code>/am help</> for a list.",
+        &["subcommand"],
+    ),
+    (
+        "command-usage",
+        "Usage: <code>/am {usage}</>",
+        &["usage"],
+    ),
+    (
+        "command-unauthorized",
+        "You don't have permission to use <code>/am</>.",
+        &[],
+    ),
+    (
+        "command-help-row",
+        "<code>/am {usage}</>: {description}",
+        &["usage", "description"],
+    ),
+    (
+        "command-failed",
+        "<color=\"f00\">Something went wrong:</> {error}",
+        &["error"],
+    ),
+    ("command-info", "<b>anti-microbrick</> v{version}", &["version"]),
+    (
+        "command-info-connected",
+        "Autosave: connected to {plugin} {ago}",
+        &["plugin", "ago"],
+    ),
+    (
+        "command-info-not-connected",
+        "Autosave: <color=\"f00\">not connected</> (tried {plugins})",
+        &["plugins"],
+    ),
+    (
+        "command-info-last-emit",
+        "Last save received {ago}",
+        &["ago"],
+    ),
+    (
+        "command-info-no-emit",
+        "No saves received since the plugin started",
+        &[],
+    ),
+    (
+        "command-info-last-scan",
+        "Last scan {ago}: {flagged} warned, {cleared} cleared, {removed} microbricks removed",
+        &["ago", "flagged", "cleared", "removed"],
+    ),
+    (
+        "command-info-last-scan-failed",
+        "Last scan {ago}: <color=\"f00\">failed:</> {error}",
+        &["ago", "error"],
+    ),
+    (
+        "command-info-no-scan",
+        "No scans since the plugin started",
+        &[],
+    ),
+    ("command-info-enforcing", "Enforcement: on", &[]),
+    (
+        "command-info-paused",
+        "Enforcement: <color=\"ff0\">paused</>",
+        &[],
+    ),
+    (
+        "command-info-dry-run",
+        "Enforcement: <color=\"ff0\">dry run</>",
+        &[],
+    ),
+    (
+        "command-status-no-player",
+        "Please specify a player to look up, by name or id.",
+        &[],
+    ),
+    (
+        "command-status-none",
+        "<b>{player}</> has no record.",
+        &["player"],
+    ),
+    ("command-status", "<b>{player}</> ({id}):", &["player", "id"]),
+    (
+        "command-status-timer",
+        "Timer: microbricks cleared in <b>{minutes}</> minutes",
+        &["minutes"],
+    ),
+    (
+        "command-status-category-timer",
+        "Timer: {category} bricks cleared in <b>{minutes}</> minutes",
+        &["category", "minutes"],
+    ),
+    ("command-status-no-timer", "Timer: none", &[]),
+    (
+        "command-status-assets",
+        "Last flagged {ago}, for {assets}",
+        &["ago", "assets"],
+    ),
+    (
+        "command-status-watched",
+        "Watched: any microbrick is cleared on sight",
+        &[],
+    ),
+    (
+        "command-status-permit",
+        "Permit: microbricks allowed for {duration} more, until {time}",
+        &["duration", "time"],
+    ),
+    (
+        "command-status-pending",
+        "Clear pending: seen by {scans} of {required} scans",
+        &["scans", "required"],
+    ),
+    (
+        "command-status-violations",
+        "Violations: <b>{violations}</> of {max}",
+        &["violations", "max"],
+    ),
+    (
+        "command-status-bans",
+        "Bans: <b>{bans}</> of {max}",
+        &["bans", "max"],
+    ),
+    (
+        "command-expires-no-player",
+        "Please specify a player to look up, by name or id.",
+        &[],
+    ),
+    (
+        "command-expires-none",
+        "<b>{player}</> has no active warning.",
+        &["player"],
+    ),
+    (
+        "command-expires",
+        "<b>{player}</>'s microbricks expire in <b>{remaining}</>.",
+        &["player", "remaining"],
+    ),
+    (
+        "command-expires-passed",
+        "<b>{player}</>'s timer is up, their microbricks will be cleared on the next scan.",
+        &["player"],
+    ),
+    (
+        "command-expires-target",
+        "Your microbricks will be cleared in <b>{remaining}</> unless you remove them.",
+        &["remaining"],
+    ),
+    (
+        "command-expires-target-passed",
+        "Your time is up: your microbricks will be cleared on the next scan.",
+        &[],
+    ),
+    (
+        "command-expires-category",
+        "<b>{player}</>'s {category} bricks expire in <b>{remaining}</>.",
+        &["player", "category", "remaining"],
+    ),
+    (
+        "command-expires-category-passed",
+        "<b>{player}</>'s {category} timer is up, those bricks will be cleared on the next scan.",
+        &["player", "category"],
+    ),
+    (
+        "command-expires-target-category",
+        "Your {category} bricks will be cleared in <b>{remaining}</> unless you remove some.",
+        &["category", "remaining"],
+    ),
+    (
+        "command-expires-target-category-passed",
+        "Your time is up: your {category} bricks will be cleared on the next scan.",
+        &["category"],
+    ),
+    ("command-list-empty", "Nobody is being tracked.", &[]),
+    (
+        "command-list-row",
+        "<b>{player}</>: {timer}, {violations} violations, {bans} bans",
+        &["player", "timer", "violations", "bans"],
+    ),
+    ("command-list-watched", " (watched)", &[]),
+    ("command-list-timer", "cleared in {minutes}m", &["minutes"]),
+    (
+        "command-list-category-timer",
+        "{category} cleared in {minutes}m",
+        &["category", "minutes"],
+    ),
+    ("command-list-no-timer", "no timer", &[]),
+    (
+        "command-page-more",
+        "Page {page}/{pages}, <code>/am {command} {next}</> for more.",
+        &["page", "pages", "command", "next"],
+    ),
+    ("command-page-last", "Page {page}/{pages}.", &["page", "pages"]),
+    (
+        "command-page-invalid",
+        "That page doesn't exist. There are {pages} pages.",
+        &["pages"],
+    ),
+    ("command-log-empty", "Nothing has been logged yet.", &[]),
+    (
+        "command-log-invalid",
+        "<b>{count}</> isn't a number of entries to show.",
+        &["count"],
+    ),
+    (
+        "command-log",
+        "The last {count} of {total} logged actions, newest first:",
+        &["count", "total"],
+    ),
+    (
+        "command-log-warn",
+        "  {ago}: <b>{player}</> was warned about {count} microbricks",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-clear",
+        "  {ago}: <b>{player}</> had {count} microbricks cleared",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-kick",
+        "  {ago}: <b>{player}</> was kicked at {count} violations",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-ban",
+        "  {ago}: <b>{player}</> was banned at {count} violations",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-pardon",
+        "  {ago}: {by} pardoned {count} of <b>{player}</>'s violations",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-unban",
+        "  {ago}: {by} unbanned <b>{player}</>, leaving {count} bans",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-undo",
+        "  {ago}: {by} undid <b>{player}</>'s clear, taking back {count} violations",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-log-clean",
+        "  {ago}: {by} cleaned <b>{player}</>'s record",
+        &["ago", "player", "count", "by"],
+    ),
+    (
+        "command-list-total",
+        "{count} players tracked.",
+        &["count"],
+    ),
+    ("command-scan-busy", "A scan is already running.", &[]),
+    (
+        "command-scan-started",
+        "Saving and scanning the world...",
+        &[],
+    ),
+    (
+        "command-scan-clean",
+        "Scan complete, no microbricks to enforce.",
+        &[],
+    ),
+    (
+        "command-scan-result",
+        "Scan complete: <b>{flagged}</> players warned, <b>{cleared}</> cleared, <b>{removed}</> microbricks removed.",
+        &["flagged", "cleared", "removed"],
+    ),
+    (
+        "command-check-no-player",
+        "Please specify a player to check, by name or id.",
+        &[],
+    ),
+    (
+        "command-check-started",
+        "Saving the world and checking <b>{player}</>...",
+        &["player"],
+    ),
+    (
+        "command-check-clean",
+        "<b>{player}</> has no microbricks.",
+        &["player"],
+    ),
+    (
+        "command-check",
+        "<b>{player}</> has <b>{count}</> microbricks (allowed {max}):",
+        &["player", "count", "max"],
+    ),
+    ("command-check-asset", "  {asset}: {count}", &["asset", "count"]),
+    (
+        "command-check-over",
+        "A scan would act on them: {reason}.",
+        &["reason"],
+    ),
+    (
+        "command-check-within",
+        "That's within the limits, so a scan would leave them alone.",
+        &[],
+    ),
+    (
+        "command-check-expired",
+        "Their timer is up. Run <code>/am check {player} clear</> to clear them now.",
+        &["player"],
+    ),
+    (
+        "command-check-not-expired",
+        "Their timer isn't up yet, so nothing was cleared.",
+        &[],
+    ),
+    (
+        "command-check-cleared",
+        "Cleared <b>{player}</>, removing <b>{removed}</> microbricks.",
+        &["player", "removed"],
+    ),
+    (
+        "command-check-not-cleared",
+        "<b>{player}</> wasn't cleared (exempt, within budget, or awaiting confirmation).",
+        &["player"],
+    ),
+    (
+        "command-goto-no-player",
+        "Please specify a player to go to, by name or id.",
+        &[],
+    ),
+    (
+        "command-goto-none",
+        "<b>{player}</> hasn't been flagged for microbricks.",
+        &["player"],
+    ),
+    (
+        "command-goto-stale",
+        "<color=\"ff0\">These positions are from a scan {ago}</>, the bricks may have moved since.",
+        &["ago"],
+    ),
+    (
+        "command-goto-list",
+        "<b>{player}</> has {count} clusters of microbricks. Run <code>/am goto {player} (index)</> to go to one:",
+        &["player", "count"],
+    ),
+    (
+        "command-goto-row",
+        "  {index}. {count} around ({x}, {y}, {z})",
+        &["index", "count", "x", "y", "z"],
+    ),
+    (
+        "command-goto-invalid",
+        "Please give an index from 1 to {count}.",
+        &["count"],
+    ),
+    (
+        "command-goto",
+        "Teleported to {count} of <b>{player}</>'s microbricks at ({x}, {y}, {z}).",
+        &["player", "count", "x", "y", "z"],
+    ),
+    (
+        "command-find-started",
+        "Saving the world and finding microbricks...",
+        &[],
+    ),
+    ("command-find-clean", "There are no microbricks in the world.", &[]),
+    (
+        "command-find",
+        "Found <b>{count}</> microbricks of {assets} assets, out of {bricks} bricks. Nothing was enforced.",
+        &["count", "assets", "bricks"],
+    ),
+    (
+        "command-find-row",
+        "  <b>{asset}</>: {count} ({owners})",
+        &["asset", "count", "owners"],
+    ),
+    (
+        "command-find-minigames",
+        "  Plus {count} owned by running minigames, which are never enforced.",
+        &["count"],
+    ),
+    ("command-find-public", "PUBLIC", &[]),
+    (
+        "command-simulate-invalid",
+        "<b>{save}</> isn't a save in the Builds directory.",
+        &["save"],
+    ),
+    (
+        "command-simulate-missing",
+        "There's no save named <b>{save}</>.",
+        &["save"],
+    ),
+    (
+        "command-simulate-started",
+        "Simulating a scan of <b>{save}</>...",
+        &["save"],
+    ),
+    (
+        "command-simulate-clean",
+        "<b>{save}</> has no microbricks.",
+        &["save"],
+    ),
+    (
+        "command-simulate",
+        "<b>{save}</> has <b>{count}</> microbricks from {owners} owners, out of {bricks} bricks. Nothing was enforced.",
+        &["save", "count", "owners", "bricks"],
+    ),
+    (
+        "command-simulate-row",
+        "  <b>{player}</>: {count} ({assets})",
+        &["player", "count", "assets"],
+    ),
+    ("command-simulate-public", "PUBLIC", &[]),
+    (
+        "command-history-no-player",
+        "Please specify a player to show the history of, by name or id.",
+        &[],
+    ),
+    (
+        "command-history-none",
+        "<b>{player}</> has never been cleared.",
+        &["player"],
+    ),
+    (
+        "command-history",
+        "<b>{player}</> has {count} entries on their history:",
+        &["player", "count"],
+    ),
+    (
+        "command-history-row",
+        "{ago}: <b>{removed}</> microbricks removed, kept as <code>{save}</>",
+        &["ago", "removed", "save"],
+    ),
+    (
+        "command-history-row-unsaved",
+        "{ago}: <b>{removed}</> microbricks removed",
+        &["ago", "removed"],
+    ),
+    (
+        "command-history-hidden",
+        " <color=\"a00\">(hidden)</>",
+        &[],
+    ),
+    (
+        "command-history-category",
+        " <color=\"ff0\">({category})</>",
+        &["category"],
+    ),
+    (
+        "command-history-concealed",
+        " <color=\"a00\">(concealed)</>",
+        &[],
+    ),
+    ("command-history-legacy", "legacy violation", &[]),
+    ("command-history-ago", "{duration} ago", &["duration"]),
+    (
+        "command-set-no-player",
+        "Please specify a player, by name or id.",
+        &[],
+    ),
+    (
+        "command-set-invalid",
+        "Please give a count from 0 to {max}.",
+        &["max"],
+    ),
+    (
+        "command-set-violations",
+        "Set <b>{player}</>'s violations from {previous} to <b>{count}</>.",
+        &["player", "previous", "count"],
+    ),
+    (
+        "command-set-bans",
+        "Set <b>{player}</>'s bans from {previous} to <b>{count}</>.",
+        &["player", "previous", "count"],
+    ),
+    (
+        "command-unban-no-player",
+        "Please specify a player to unban, by name or id.",
+        &[],
+    ),
+    (
+        "command-unban",
+        "Unbanned <b>{player}</>, and set their bans from {previous} to <b>{bans}</>.",
+        &["player", "previous", "bans"],
+    ),
+    (
+        "command-clearbans-no-player",
+        "Please specify a player to clear the bans of, by name or id.",
+        &[],
+    ),
+    (
+        "command-clearbans-none",
+        "<b>{player}</> has no bans to clear.",
+        &["player"],
+    ),
+    (
+        "command-clearbans",
+        "Cleared <b>{player}</>'s {previous} bans. Their violations are untouched.",
+        &["player", "previous"],
+    ),
+    (
+        "command-stats",
+        "<b>{scans}</> scans, <b>{clears}</> clears, <b>{removed}</> microbricks removed, <b>{bans}</> bans issued.",
+        &["scans", "clears", "removed", "bans"],
+    ),
+    (
+        "command-stats-last-scan",
+        "Last scan {ago}, {bricks} bricks scanned.",
+        &["ago", "bricks"],
+    ),
+    ("command-stats-never", "No saves have been scanned yet.", &[]),
+    ("command-stats-reset", "OK, stats reset.", &[]),
+    (
+        "command-permit-usage",
+        "Usage: <code>/am permit (player) (duration)</>, with a duration like 2h, 30m, or 1d.",
+        &[],
+    ),
+    (
+        "command-watch-no-player",
+        "Please specify a player to watch or unwatch, by name or id.",
+        &[],
+    ),
+    (
+        "command-watch",
+        "Watching <b>{player}</>. Their microbricks are cleared on sight until <code>/am unwatch</>.",
+        &["player"],
+    ),
+    (
+        "command-unwatch",
+        "Stopped watching <b>{player}</>.",
+        &["player"],
+    ),
+    (
+        "command-unwatch-not-watched",
+        "<b>{player}</> isn't being watched.",
+        &["player"],
+    ),
+    (
+        "command-permit-no-player",
+        "Please specify a player to permit, by name or id.",
+        &[],
+    ),
+    (
+        "command-permit",
+        "<b>{player}</> may use microbricks for {duration}, until {time}.",
+        &["player", "duration", "time"],
+    ),
+    (
+        "command-permit-target",
+        "An admin has allowed you to use microbricks for <b>{duration}</>. They'll be enforced as usual after that.",
+        &["duration"],
+    ),
+    (
+        "command-allowasset-usage",
+        "Usage: <code>/am allowasset add|remove|list [asset]</>",
+        &[],
+    ),
+    (
+        "command-allowasset-add",
+        "Assets matching <b>{asset}</> are now allowed.",
+        &["asset"],
+    ),
+    (
+        "command-allowasset-remove",
+        "Assets matching <b>{asset}</> are no longer allowed.",
+        &["asset"],
+    ),
+    (
+        "command-allowasset-not-found",
+        "<b>{asset}</> isn't on the allowlist.",
+        &["asset"],
+    ),
+    (
+        "command-allowasset-list",
+        "Allowed with /am allowasset: {assets}",
+        &["assets"],
+    ),
+    (
+        "command-allowasset-list-config",
+        "Allowed in the config: {assets}",
+        &["assets"],
+    ),
+    ("command-allowasset-none", "none", &[]),
+    (
+        "command-pardon-no-player",
+        "Please specify a player to pardon, by name or id.",
+        &[],
+    ),
+    (
+        "command-pardon",
+        "Pardoned <b>{player}</>, who now has <b>{violations}</> violations.",
+        &["player", "violations"],
+    ),
+    (
+        "command-pardon-target",
+        "An admin pardoned you. You now have <b>{violations}</> microbrick violations.",
+        &["violations"],
+    ),
+    (
+        "command-exempt-usage",
+        "Usage: <code>/am exempt add (player)</>, <code>/am exempt remove (player)</>, or <code>/am exempt list</>",
+        &[],
+    ),
+    (
+        "command-exempt-no-player",
+        "Please specify an online player to exempt.",
+        &[],
+    ),
+    (
+        "command-exempt-add",
+        "<b>{player}</> is now exempt from microbrick enforcement.",
+        &["player"],
+    ),
+    (
+        "command-exempt-remove",
+        "<b>{player}</> is no longer exempt.",
+        &["player"],
+    ),
+    (
+        "command-exempt-not-found",
+        "<b>{player}</> isn't exempted with <code>/am exempt</>.",
+        &["player"],
+    ),
+    (
+        "command-exempt-list",
+        "Exempt players: {players}",
+        &["players"],
+    ),
+    (
+        "command-exempt-list-empty",
+        "Nobody is exempted with <code>/am exempt</>.",
+        &[],
+    ),
+    ("command-top-empty", "Nobody has any violations or bans.", &[]),
+    (
+        "command-top-row",
+        "{rank}. <b>{player}</>: {violations} violations, {bans} bans",
+        &["rank", "player", "violations", "bans"],
+    ),
+    (
+        "command-top-total",
+        "{count} players with records.",
+        &["count"],
+    ),
+    (
+        "command-pause",
+        "Microbrick enforcement is <b>paused</>. Saves won't be checked until <code>/am resume</>.",
+        &[],
+    ),
+    (
+        "command-resume",
+        "Microbrick enforcement has resumed.",
+        &[],
+    ),
+    (
+        "command-dryrun-on",
+        "Dry-run mode is <b>on</>. Scans only report what they would do to admins.",
+        &[],
+    ),
+    (
+        "command-dryrun-off",
+        "Dry-run mode is <b>off</>. Scans enforce as usual.",
+        &[],
+    ),
+    (
+        "command-dryrun-usage",
+        "Usage: <code>/am dryrun on|off</>",
+        &[],
+    ),
+    (
+        "command-notify-on",
+        "Your admin notifications are <b>on</>.",
+        &[],
+    ),
+    (
+        "command-notify-off",
+        "Your admin notifications are <b>off</>. Run <code>/am notify on</> to get them again.",
+        &[],
+    ),
+    (
+        "command-notify-usage",
+        "Usage: <code>/am notify on|off</>",
+        &[],
+    ),
+    (
+        "command-paused-notice",
+        "<color=\"ff0\">Enforcement is paused.</> Run <code>/am resume</> to turn it back on.",
+        &[],
+    ),
+    (
+        "command-restore-no-player",
+        "Please specify a player to restore, by name or id.",
+        &[],
+    ),
+    (
+        "command-restore-none",
+        "No removed microbricks to restore for <b>{player}</> (they have {count} saved).",
+        &["player", "count"],
+    ),
+    (
+        "command-restore",
+        "Restored <b>{player}</>'s microbricks removed at {time}. They now have <b>{violations}</> violations.",
+        &["player", "time", "violations"],
+    ),
+    (
+        "command-quarantine-usage",
+        "Usage: <code>/am quarantine list [player]</> or <code>/am quarantine purge (days)</>",
+        &[],
+    ),
+    (
+        "command-quarantine-no-player",
+        "Please specify a player to list the removed microbricks of, by name or id.",
+        &[],
+    ),
+    (
+        "command-quarantine-empty",
+        "No removed microbricks have been kept.",
+        &[],
+    ),
+    (
+        "command-quarantine-owner",
+        "<b>{player}</> ({count} saved):",
+        &["player", "count"],
+    ),
+    (
+        "command-quarantine-row",
+        "  {index}. {time}: {bricks} bricks",
+        &["index", "time", "bricks"],
+    ),
+    (
+        "command-quarantine-total",
+        "{count} saves of removed microbricks.",
+        &["count"],
+    ),
+    (
+        "command-quarantine-purge",
+        "Deleted <b>{count}</> saves of removed microbricks older than {days} days, freeing {megabytes} MB.",
+        &["count", "days", "megabytes"],
+    ),
+    ("command-undo-none", "There are no clears left to undo.", &[]),
+    (
+        "command-undo-missing",
+        "Can't undo the clear of <b>{player}</> at {time}: its removed microbricks weren't kept or have been purged. Run <code>/am undo</> again to move on to the clear before it.",
+        &["player", "time"],
+    ),
+    (
+        "command-undo",
+        "Undid the clear of <b>{player}</> at {time}, loading their microbricks back in. They now have <b>{violations}</> violations.",
+        &["player", "time", "violations"],
+    ),
+    (
+        "command-undo-banned",
+        "<b>{player}</> was banned for this clear. Run <code>/am unban {id}</> to lift it.",
+        &["player", "id"],
+    ),
+    (
+        "command-threshold-usage",
+        "Usage: <code>/am threshold (name) (value)</>, <code>/am threshold reset (name)</>, or <code>/am threshold list</>. Settings: {names}",
+        &["names"],
+    ),
+    (
+        "command-threshold-set",
+        "<b>{name}</> is now <b>{value}</> until reset.",
+        &["name", "value"],
+    ),
+    (
+        "command-threshold-invalid",
+        "Couldn't override that: {error}",
+        &["error"],
+    ),
+    (
+        "command-threshold-reset",
+        "<b>{name}</> is back to the config's <b>{value}</>.",
+        &["name", "value"],
+    ),
+    (
+        "command-threshold-not-overridden",
+        "<b>{name}</> isn't overridden.",
+        &["name"],
+    ),
+    (
+        "command-threshold-list-empty",
+        "No settings are overridden.",
+        &[],
+    ),
+    (
+        "command-threshold-row",
+        "<b>{name}</>: {value} (config: {file})",
+        &["name", "value", "file"],
+    ),
+    ("command-config-row", "<b>{key}</>: {value}", &["key", "value"]),
+    ("command-config-count", "{count} entries", &["count"]),
+    (
+        "command-config-override",
+        "{value} <color=\"ff0\">(overridden with /am threshold, config: {file})</>",
+        &["value", "file"],
+    ),
+    (
+        "command-config-full",
+        "Player lists are summarized and messages are hidden. Run <code>/am config full</> to see everything.",
+        &[],
+    ),
+    (
+        "reload-failed",
+        "Failed to load the config: {error}",
+        &["error"],
+    ),
+    (
+        "reload-invalid",
+        "<color=\"f00\">The new config is invalid, keeping the old one.</>",
+        &[],
+    ),
+    ("reload-error", "<color=\"f00\">Error:</> {error}", &["error"]),
+    (
+        "reload-warning",
+        "<color=\"ff0\">Warning:</> {warning}",
+        &["warning"],
+    ),
+    (
+        "reload-unchanged",
+        "Reloaded the config, nothing changed.",
+        &[],
+    ),
+    (
+        "reload-changed",
+        "Reloaded the config, {count} fields changed:",
+        &["count"],
+    ),
+];
+
+/// Player-facing message templates.
+///
+/// Each template may contain `{placeholder}`s which are substituted when the message is sent.
+/// Templates left empty in the config fall back to `messages.json`, then to the built-in text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Messages {
+    /// Whispered to a player when they have too many microbricks.
+    ///
+    /// Placeholders: `{player}`, `{count}`, `{total}`, `{reason}`, `{minutes}`, `{locations}`.
+    #[serde(rename = "message-warning", default)]
+    pub warning: String,
+
+    /// Whispered to a player who only has bricks under `warn` rules, which are never cleared.
+    ///
+    /// Placeholders: `{player}`, `{count}`, `{total}`, `{reason}`, `{locations}`.
+    #[serde(rename = "message-warning-only", default)]
+    pub warning_only: String,
+
+    /// Broadcast when a player's microbricks are cleared.
+    ///
+    /// Placeholders: `{player}`, `{count}`.
+    #[serde(rename = "message-clear", default)]
+    pub clear: String,
+
+    /// Broadcast when a player's microbricks are converted, in `convert-instead-of-clear` mode.
+    ///
+    /// Placeholders: `{player}`, `{count}`.
+    #[serde(rename = "message-convert", default)]
+    pub convert: String,
+
+    /// Broadcast when public microbricks are cleared.
+    ///
+    /// Placeholders: `{count}`.
+    #[serde(rename = "message-public-clear", default)]
+    pub public_clear: String,
+
+    /// Broadcast when public microbricks are converted, in `convert-instead-of-clear` mode.
+    ///
+    /// Placeholders: `{count}`.
+    #[serde(rename = "message-public-convert", default)]
+    pub public_convert: String,
+
+    /// Whispered to a player after their microbricks are cleared, if they were not banned.
+    ///
+    /// Placeholders: `{player}`, `{violations}`, `{added}`, `{max}`.
+    #[serde(rename = "message-violations", default)]
+    pub violations: String,
+
+    /// Whispered to a player after their microbricks are cleared when bans are disabled.
+    ///
+    /// Placeholders: `{player}`, `{violations}`, `{added}`.
+    #[serde(rename = "message-violations-no-bans", default)]
+    pub violations_no_bans: String,
+
+    /// The reason given when a player is kicked instead of banned.
+    ///
+    /// Placeholders: `{violations}`.
+    #[serde(rename = "kick-reason", default)]
+    pub kick_reason: String,
+
+    /// Announced when a player is banned.
+    ///
+    /// Placeholders: `{player}`, `{duration}`.
+    #[serde(rename = "message-ban", default)]
+    pub ban: String,
+
+    /// The reason given when a player is banned. When empty, a reason is picked
+    /// depending on whether the ban is permanent.
+    ///
+    /// Placeholders: `{violations}`, `{bans}`, `{remaining}`, `{duration}`.
+    #[serde(rename = "ban-reason", default)]
+    pub ban_reason: String,
+
+    /// Strings overridden by `messages.json`, by key.
+    #[serde(skip)]
+    pub overrides: HashMap<String, String>,
+}
+
+impl Messages {
+    /// Read the string overrides from `messages.json`, if it exists.
+    pub fn load_overrides(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(());
+        }
+
+        self.overrides = serde_json::from_reader(File::open(path)?)
+            .with_context(|| format!("invalid {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Replace any empty templates with their `messages.json` or built-in text.
+    pub fn fill_defaults(&mut self) {
+        for (key, template) in [
+            ("message-warning", &mut self.warning),
+            ("message-warning-only", &mut self.warning_only),
+            ("message-clear", &mut self.clear),
+            ("message-convert", &mut self.convert),
+            ("message-public-clear", &mut self.public_clear),
+            ("message-public-convert", &mut self.public_convert),
+            ("message-violations", &mut self.violations),
+            ("message-violations-no-bans", &mut self.violations_no_bans),
+            ("kick-reason", &mut self.kick_reason),
+            ("message-ban", &mut self.ban),
+        ] {
+            if template.is_empty() {
+                *template = lookup(&self.overrides, key).to_string();
+            }
+        }
+    }
+
+    /// Get a string by key, preferring its `messages.json` override.
+    pub fn get(&self, key: &str) -> &str {
+        lookup(&self.overrides, key)
+    }
+
+    /// Get a string by key, falling back to `default` for strings without built-in text.
+    pub fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.overrides
+            .get(key)
+            .map(String::as_str)
+            .unwrap_or(default)
+    }
+
+    /// Get a string by key and substitute its placeholders.
+    pub fn text(&self, key: &str, vars: &[(&str, &dyn Display)]) -> String {
+        render(self.get(key), vars)
+    }
+
+    /// The template announcing a player's microbricks are cleared, or converted in convert mode.
+    pub fn clear(&self, convert: bool) -> &str {
+        if convert {
+            &self.convert
+        } else {
+            &self.clear
+        }
+    }
+
+    /// The template announcing public microbricks are cleared, or converted in convert mode.
+    pub fn public_clear(&self, convert: bool) -> &str {
+        if convert {
+            &self.public_convert
+        } else {
+            &self.public_clear
+        }
+    }
+
+    /// The violations template to use, depending on whether bans are enabled.
+    pub fn violations(&self, bans_enabled: bool) -> &str {
+        if bans_enabled {
+            &self.violations
+        } else {
+            &self.violations_no_bans
+        }
+    }
+
+    /// The ban reason template to use for a ban.
+    pub fn ban_reason(&self, permanent: bool) -> &str {
+        match self.ban_reason.as_str() {
+            "" if permanent => self.get("ban-reason-permanent"),
+            "" => self.get("ban-reason-temporary"),
+            reason => reason,
+        }
+    }
+
+    /// Check each template and override for placeholders it doesn't support.
+    pub fn validate(&self, issues: &mut Vec<Issue>) {
+        for (key, template) in [
+            ("message-warning", &self.warning),
+            ("message-warning-only", &self.warning_only),
+            ("message-clear", &self.clear),
+            ("message-convert", &self.convert),
+            ("message-public-clear", &self.public_clear),
+            ("message-public-convert", &self.public_convert),
+            ("message-violations", &self.violations),
+            ("message-violations-no-bans", &self.violations_no_bans),
+            ("kick-reason", &self.kick_reason),
+            ("message-ban", &self.ban),
+        ] {
+            check_template(issues, key, template, placeholders(key));
+        }
+        check_template(
+            issues,
+            "ban-reason",
+            &self.ban_reason,
+            placeholders("ban-reason-temporary"),
+        );
+
+        for (key, template) in self.overrides.iter() {
+            // subcommand descriptions live with the subcommands, and have no placeholders
+            if key.starts_with("help-") {
+                continue;
+            }
+
+            // categories can each override their messages, like `category-warning-lights`
+            let base = ["category-warning", "category-clear"]
+                .iter()
+                .copied()
+                .find(|base| {
+                    key.strip_prefix(base)
+                        .is_some_and(|name| name.starts_with('-'))
+                })
+                .unwrap_or(key);
+
+            match STRINGS.iter().find(|(k, _, _)| *k == base) {
+                Some((_, _, allowed)) => {
+                    check_template(issues, &format!("messages.json {}", key), template, allowed)
+                }
+                None => issues.push(Issue::Warning(format!(
+                    "messages.json has unknown key {}",
+                    key
+                ))),
+            }
+        }
+    }
+}
+
+/// Get a string's override, falling back to its built-in text.
+fn lookup<'a>(overrides: &'a HashMap<String, String>, key: &str) -> &'a str {
+    if let Some(text) = overrides.get(key) {
+        return text;
+    }
+
+    STRINGS
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, text, _)| *text)
+        .unwrap_or("")
+}
+
+/// The placeholders a string supports.
+fn placeholders(key: &str) -> &'static [&'static str] {
+    STRINGS
+        .iter()
+        .find(|(k, _, _)| *k == key)
+        .map(|(_, _, allowed)| *allowed)
+        .unwrap_or(&[])
+}
+
+/// Substitute each `{name}` in a template with its value.
+pub fn render(template: &str, vars: &[(&str, &dyn Display)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in vars {
+        out = out.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    out
+}
+
+/// Escape a string so it can be passed as a quoted argument to a console command.
+pub fn escape_command_arg(arg: &str) -> String {
+    arg.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace(['\n', '\r'], " ")
+}
+
+/// Report unknown or unterminated placeholders in a template.
+fn check_template(issues: &mut Vec<Issue>, field: &str, template: &str, allowed: &[&str]) {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        match rest.find('}') {
+            Some(end) => {
+                let name = &rest[..end];
+                if !allowed.contains(&name) {
+                    issues.push(Issue::Warning(format!(
+                        "{} uses unknown placeholder {{{}}} (expected one of {})",
+                        field,
+                        name,
+                        allowed
+                            .iter()
+                            .map(|a| format!("{{{}}}", a))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )));
+                }
+                rest = &rest[end + 1..];
+            }
+            None => {
+                issues.push(Issue::Warning(format!(
+                    "{} has an unterminated placeholder",
+                    field
+                )));
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    path::Path,
+};
+
+use anyhow::Result;
+use brickadia::{
+    read::SaveReader,
+    save::{Brick, BrickOwner, Component, Header1, Header2, Preview, SaveData},
+    write::SaveWriter,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// The directory removed microbricks are kept in, relative to the saves directory
+/// so they can be loaded back in by name.
+pub const QUARANTINE_DIR: &str = "anti_microbrick_quarantine";
+
+/// A save of one owner's removed microbricks.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// The name to load the snapshot by, relative to the saves directory.
+    pub name: String,
+
+    /// The id of the owner whose bricks were removed.
+    pub owner: String,
+
+    /// When the bricks were removed, as a unix timestamp.
+    pub time: u64,
+}
+
+/// Build a snapshot of an owner's removed bricks, cut out of the save they were removed from.
+///
+/// The snapshot's owner list is pruned down to just that owner.
+///
+/// `components` are the components of the whole save, of which only those on the
+/// removed bricks are kept. The save's preview is kept as is, to tell snapshots apart.
+pub fn snapshot(
+    header1: &Header1,
+    header2: &Header2,
+    preview: &Preview,
+    components: &HashMap<String, Component>,
+    mut bricks: Vec<Brick>,
+) -> SaveData {
+    let mut header2 = copy_header2(header2);
+    prune_owners(&mut header2, &mut bricks);
+
+    SaveData {
+        header1: copy_header1(header1),
+        header2,
+        preview: preview.clone(),
+        components: copy_components(components, &bricks),
+        bricks,
+        ..Default::default()
+    }
+}
+
+/// Write a snapshot of an owner's removed bricks, returning its name.
+///
+/// Snapshots are named `{owner}_{time}_{kind}`, where `kind` is what the bricks were removed
+/// for, so one scan clearing an owner for several kinds of bricks keeps each apart.
+///
+/// The save is written on a blocking thread, off the async runtime.
+pub async fn write(
+    config: &Config,
+    owner: Uuid,
+    kind: &str,
+    save_data: SaveData,
+) -> Result<String> {
+    let dir = Path::new(&config.saves_dir).join(QUARANTINE_DIR);
+    let time = Utc::now().timestamp();
+    let file = format!("{}_{}_{}", owner, time, kind);
+    let path = dir.join(format!("{}.brs", file));
+
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        fs::create_dir_all(&dir)?;
+        SaveWriter::new(File::create(path)?, save_data).write()?;
+        Ok(())
+    })
+    .await??;
+
+    Ok(format!("{}/{}", QUARANTINE_DIR, file))
+}
+
+/// Rebuild a save's owner list from the owners its bricks actually have,
+/// pointing each brick at its owner's new index and counting up their bricks.
+pub fn prune_owners(header2: &mut Header2, bricks: &mut [Brick]) {
+    let mut owners: Vec<BrickOwner> = vec![];
+    let mut new_indices = vec![0; header2.brick_owners.len() + 1];
+    for brick in bricks.iter_mut() {
+        // bricks pointing past the owner list are left to PUBLIC
+        let old = match brick.owner_index as usize {
+            0 => continue,
+            n if n > header2.brick_owners.len() => {
+                brick.owner_index = 0;
+                continue;
+            }
+            n => n,
+        };
+
+        if new_indices[old] == 0 {
+            owners.push(BrickOwner {
+                bricks: 0,
+                ..header2.brick_owners[old - 1].clone()
+            });
+            new_indices[old] = owners.len() as u32;
+        }
+
+        brick.owner_index = new_indices[old];
+        owners[new_indices[old] as usize - 1].bricks += 1;
+    }
+    header2.brick_owners = owners;
+}
+
+/// Find an owner's snapshots, newest first.
+pub fn list(config: &Config, owner: &str) -> Result<Vec<Snapshot>> {
+    let mut snapshots = list_all(config)?;
+    snapshots.retain(|s| s.owner == owner);
+    Ok(snapshots)
+}
+
+/// Find every snapshot, newest first.
+pub fn list_all(config: &Config) -> Result<Vec<Snapshot>> {
+    let dir = Path::new(&config.saves_dir).join(QUARANTINE_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut snapshots = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let stem = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) if path.extension().and_then(|e| e.to_str()) == Some("brs") => stem,
+            _ => continue,
+        };
+
+        let (owner, time) = match parse_name(stem) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+
+        snapshots.push(Snapshot {
+            name: format!("{}/{}", QUARANTINE_DIR, stem),
+            owner: owner.to_string(),
+            time,
+        });
+    }
+
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.time));
+    Ok(snapshots)
+}
+
+/// Split a snapshot's file name into its owner and time.
+///
+/// Owner ids never contain `_`, but kinds can, and snapshots from before kinds
+/// were added to the name have none.
+fn parse_name(stem: &str) -> Option<(&str, u64)> {
+    let mut parts = stem.splitn(3, '_');
+    let owner = parts.next()?;
+    let time = parts.next()?.parse().ok()?;
+    Some((owner, time))
+}
+
+/// Delete every snapshot older than `days`, returning how many were deleted and their total size in bytes.
+pub fn purge(config: &Config, days: f32) -> Result<(usize, u64)> {
+    let cutoff = (Utc::now().timestamp() as u64).saturating_sub((days * 60. * 60. * 24.) as u64);
+
+    let (mut count, mut bytes) = (0, 0);
+    for snapshot in list_all(config)?.iter().filter(|s| s.time < cutoff) {
+        let path = Path::new(&config.saves_dir).join(format!("{}.brs", snapshot.name));
+        let size = fs::metadata(&path)?.len();
+        fs::remove_file(&path)?;
+        count += 1;
+        bytes += size;
+    }
+
+    Ok((count, bytes))
+}
+
+/// Read how many bricks a snapshot holds from its header.
+pub fn brick_count(config: &Config, snapshot: &Snapshot) -> Result<u32> {
+    let path = Path::new(&config.saves_dir).join(format!("{}.brs", snapshot.name));
+    let mut reader = SaveReader::new(File::open(path)?)?;
+    Ok(reader.read_header1()?.brick_count)
+}
+
+fn copy_header1(header1: &Header1) -> Header1 {
+    Header1 {
+        map: header1.map.clone(),
+        description: header1.description.clone(),
+        author: header1.author.clone(),
+        host: header1.host.clone(),
+        save_time: header1.save_time,
+        brick_count: header1.brick_count,
+    }
+}
+
+fn copy_header2(header2: &Header2) -> Header2 {
+    Header2 {
+        mods: header2.mods.clone(),
+        brick_assets: header2.brick_assets.clone(),
+        colors: header2.colors.clone(),
+        materials: header2.materials.clone(),
+        brick_owners: header2.brick_owners.clone(),
+        physical_materials: header2.physical_materials.clone(),
+    }
+}
+
+/// Copy the components used by a set of bricks.
+///
+/// Their brick indices are left empty, since the writer works them out from the bricks.
+fn copy_components(
+    components: &HashMap<String, Component>,
+    bricks: &[Brick],
+) -> HashMap<String, Component> {
+    let names = bricks
+        .iter()
+        .flat_map(|brick| brick.components.keys())
+        .collect::<HashSet<_>>();
+    components
+        .iter()
+        .filter(|(name, _)| names.contains(name))
+        .map(|(name, component)| {
+            let component = Component {
+                version: component.version,
+                brick_indices: vec![],
+                properties: component.properties.clone(),
+            };
+            (name.clone(), component)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owner(n: u128) -> BrickOwner {
+        BrickOwner {
+            name: format!("owner{}", n),
+            id: Uuid::from_u128(n),
+            bricks: 100,
+        }
+    }
+
+    fn brick(owner_index: u32) -> Brick {
+        Brick {
+            owner_index,
+            ..Brick::default()
+        }
+    }
+
+    #[test]
+    fn parse_names() {
+        let owner = "f2d3a9b0-1c4e-4d5f-8a6b-7c8d9e0f1a2b";
+        assert_eq!(
+            parse_name(&format!("{}_1700000000_microbricks", owner)),
+            Some((owner, 1700000000))
+        );
+        assert_eq!(
+            parse_name(&format!("{}_1700000000_mod_lights", owner)),
+            Some((owner, 1700000000))
+        );
+        assert_eq!(
+            parse_name(&format!("{}_1700000000", owner)),
+            Some((owner, 1700000000))
+        );
+        assert_eq!(parse_name(owner), None);
+        assert_eq!(parse_name(&format!("{}_later", owner)), None);
+    }
+
+    #[test]
+    fn prune_owners_drops_owners_without_bricks() {
+        let mut header2 = Header2 {
+            brick_owners: (1..=4).map(owner).collect(),
+            ..Header2::default()
+        };
+
+        // owners 1 and 3 lost all their bricks, and one brick points past the owner list
+        let mut bricks = [0, 4, 2, 4, 9, 2, 4]
+            .iter()
+            .map(|&owner_index| brick(owner_index))
+            .collect::<Vec<_>>();
+        prune_owners(&mut header2, &mut bricks);
+
+        let owners = header2
+            .brick_owners
+            .iter()
+            .map(|owner| (owner.id, owner.name.as_str(), owner.bricks))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            owners,
+            [
+                (Uuid::from_u128(4), "owner4", 3),
+                (Uuid::from_u128(2), "owner2", 2),
+            ]
+        );
+
+        let indices = bricks.iter().map(|b| b.owner_index).collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1, 2, 1, 0, 2, 1]);
+    }
+
+    #[test]
+    fn prune_owners_without_owned_bricks() {
+        let mut header2 = Header2 {
+            brick_owners: (1..=2).map(owner).collect(),
+            ..Header2::default()
+        };
+        let mut bricks = vec![brick(0), brick(0)];
+        prune_owners(&mut header2, &mut bricks);
+
+        assert!(header2.brick_owners.is_empty());
+        assert!(bricks.iter().all(|b| b.owner_index == 0));
+    }
+}
@@ -0,0 +1,676 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use chrono::Utc;
+use omegga::Omegga;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::locations::Bounds;
+
+/// How many of the most recent clears `/am undo` can step back through.
+pub const RECENT_CLEARS: usize = 20;
+
+/// How many enforcement actions `/am log` keeps.
+pub const ACTION_LOG_SIZE: usize = 100;
+
+/// How long a pending clear waits for a follow-up scan before it starts over.
+pub const PENDING_EXPIRY_MINUTES: u64 = 30;
+
+/// Something done to a player, kept for `/am log`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Action {
+    /// When it happened, as a unix timestamp.
+    pub time: u64,
+
+    /// What happened.
+    pub kind: ActionKind,
+
+    /// The name of the player it happened to.
+    pub player: String,
+
+    /// How many microbricks or violations were involved, depending on the kind.
+    pub count: u32,
+
+    /// The admin who did it, for actions from admin subcommands.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub by: Option<String>,
+}
+
+/// The kind of an `Action`, also naming its `command-log-{kind}` message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ActionKind {
+    /// A player was warned and their timer started. `count` is how many microbricks were over.
+    Warn,
+
+    /// A player's microbricks were cleared. `count` is how many.
+    Clear,
+
+    /// A player was kicked. `count` is their violations.
+    Kick,
+
+    /// A player was banned. `count` is their violations.
+    Ban,
+
+    /// An admin took violations off a player's record. `count` is how many.
+    Pardon,
+
+    /// An admin unbanned a player. `count` is their bans left.
+    Unban,
+
+    /// An admin undid a player's clear. `count` is the violations taken back.
+    Undo,
+
+    /// An admin cleaned a player's record.
+    Clean,
+}
+
+impl ActionKind {
+    /// The kind's name, as it's stored.
+    pub fn name(self) -> &'static str {
+        match self {
+            ActionKind::Warn => "warn",
+            ActionKind::Clear => "clear",
+            ActionKind::Kick => "kick",
+            ActionKind::Ban => "ban",
+            ActionKind::Pardon => "pardon",
+            ActionKind::Unban => "unban",
+            ActionKind::Undo => "undo",
+            ActionKind::Clean => "clean",
+        }
+    }
+}
+
+impl Action {
+    /// An action that was just taken against `player`.
+    pub fn now(kind: ActionKind, player: &str, count: u32) -> Self {
+        Action {
+            time: Utc::now().timestamp() as u64,
+            kind,
+            player: player.to_string(),
+            count,
+            by: None,
+        }
+    }
+
+    /// The same action, taken by an admin.
+    pub fn by(self, admin: &str) -> Self {
+        Action {
+            by: Some(admin.to_string()),
+            ..self
+        }
+    }
+}
+
+/// A clear waiting on more scans to confirm the owner still has microbricks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pending {
+    /// How many consecutive scans have found microbricks after the owner's timer expired.
+    pub scans: u32,
+
+    /// When the last of those scans happened, as a unix timestamp.
+    pub time: u64,
+}
+
+/// A single violation on a player's record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    /// When the violation happened, as a unix timestamp.
+    pub time: u64,
+
+    /// Whether or not this violation was migrated from the old integer-only format,
+    /// in which case `time` is when it was migrated rather than when it happened.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub legacy: bool,
+
+    /// Whether or not it was for microbricks that were mostly invisible.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+
+    /// Whether or not it was for microbricks that were mostly buried inside larger bricks.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub concealed: bool,
+
+    /// The category of restricted bricks it was for, or `None` for microbricks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// The name of the quarantined save holding the bricks removed for it, if they were kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save: Option<String>,
+}
+
+impl Violation {
+    pub fn now() -> Self {
+        Self {
+            time: Utc::now().timestamp() as u64,
+            legacy: false,
+            hidden: false,
+            concealed: false,
+            category: None,
+            save: None,
+        }
+    }
+}
+
+/// A clear on a player's history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Clear {
+    /// When the clear happened, as a unix timestamp.
+    pub time: u64,
+
+    /// How many microbricks were removed.
+    pub removed: u32,
+
+    /// The quarantined snapshot of the removed bricks, if one was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save: Option<String>,
+
+    /// Whether or not the removed microbricks were mostly invisible.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+
+    /// Whether or not the removed microbricks were mostly buried inside larger bricks.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub concealed: bool,
+    /// The category of restricted bricks that were removed, or `None` for microbricks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+impl Clear {
+    pub fn now(removed: u32, save: Option<String>) -> Self {
+        Self {
+            time: Utc::now().timestamp() as u64,
+            removed,
+            save,
+            hidden: false,
+            concealed: false,
+            category: None,
+        }
+    }
+}
+
+/// A clear `/am undo` can reverse, kept across every owner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentClear {
+    /// The id of the owner whose bricks were cleared.
+    pub owner: String,
+
+    /// The owner's name at the time.
+    pub name: String,
+
+    /// When the clear happened, as a unix timestamp.
+    pub time: u64,
+
+    /// The quarantined snapshot of the removed bricks, if one was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save: Option<String>,
+
+    /// How many violations the clear added.
+    pub added: u32,
+
+    /// Whether or not the owner was banned for the clear.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub banned: bool,
+}
+
+/// Where an owner's microbricks were last seen, recorded by the scan that flagged them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sighting {
+    /// When the scan happened, as a unix timestamp.
+    pub time: u64,
+
+    /// How many microbricks they had, or 0 for sightings from before counts were kept.
+    #[serde(default)]
+    pub count: u32,
+
+    /// The center of each cluster of microbricks and how many are in it, largest first.
+    pub clusters: Vec<Spot>,
+
+    /// How many of their microbricks were of each asset.
+    #[serde(default)]
+    pub assets: BTreeMap<String, u32>,
+}
+
+/// A cluster of microbricks in a sighting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spot {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub count: u32,
+
+    /// The box around the cluster, for sightings from after bounds were kept.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bounds: Option<Bounds>,
+}
+
+/// Totals across every scan, kept until `/am stats reset`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    /// How many saves have been scanned.
+    pub scans: u64,
+
+    /// How many owners have had their bricks cleared.
+    pub clears: u64,
+
+    /// How many microbricks have been removed, including public ones.
+    pub removed: u64,
+
+    /// How many bans have been issued.
+    pub bans: u64,
+
+    /// When the last scan happened, as a unix timestamp.
+    pub last_scan: Option<u64>,
+
+    /// How many bricks were in the last save scanned.
+    pub last_scan_bricks: u64,
+}
+
+/// Get a player's violations, oldest first.
+///
+/// Records stored as a bare count (before violations were timestamped) are migrated
+/// to that many legacy violations timestamped now.
+pub async fn get_violations(omegga: &Omegga, id: impl ToString) -> Result<Vec<Violation>> {
+    let value = omegga
+        .store_get(format!("violations:{}", id.to_string()))
+        .await?;
+
+    Ok(match value {
+        Some(Value::Number(n)) => {
+            let now = Utc::now().timestamp() as u64;
+            (0..n.as_u64().unwrap_or(0))
+                .map(|_| Violation {
+                    time: now,
+                    legacy: true,
+                    hidden: false,
+                    concealed: false,
+                    category: None,
+                    save: None,
+                })
+                .collect()
+        }
+        Some(value @ Value::Array(_)) => serde_json::from_value(value)?,
+        _ => vec![],
+    })
+}
+
+/// Set a player's violations.
+pub fn set_violations(omegga: &Omegga, id: impl ToString, violations: &[Violation]) {
+    omegga.store_set(
+        format!("violations:{}", id.to_string()),
+        serde_json::to_value(violations).unwrap_or_default(),
+    );
+}
+
+/// Get a player's clear history, oldest first.
+pub async fn get_history(omegga: &Omegga, id: impl ToString) -> Result<Vec<Clear>> {
+    let value = omegga
+        .store_get(format!("history:{}", id.to_string()))
+        .await?;
+
+    Ok(match value {
+        Some(value @ Value::Array(_)) => serde_json::from_value(value)?,
+        _ => vec![],
+    })
+}
+
+/// Set a player's clear history.
+pub fn set_history(omegga: &Omegga, id: impl ToString, history: &[Clear]) {
+    omegga.store_set(
+        format!("history:{}", id.to_string()),
+        serde_json::to_value(history).unwrap_or_default(),
+    );
+}
+
+/// Count the violations that haven't expired yet. An expiry of 0 days never expires.
+pub fn active_violations(violations: &[Violation], expiry_days: f32) -> u32 {
+    if expiry_days <= 0. {
+        return violations.len() as u32;
+    }
+
+    let cutoff =
+        (Utc::now().timestamp() as u64).saturating_sub((expiry_days * 60. * 60. * 24.) as u64);
+    violations.iter().filter(|v| v.time >= cutoff).count() as u32
+}
+
+/// Get an owner's pending clear, if any.
+pub async fn get_pending(omegga: &Omegga, id: impl ToString) -> Result<Option<Pending>> {
+    let value = omegga
+        .store_get(format!("pending:{}", id.to_string()))
+        .await?;
+
+    Ok(match value {
+        Some(value @ Value::Object(_)) => Some(serde_json::from_value(value)?),
+        _ => None,
+    })
+}
+
+/// Set an owner's pending clear.
+pub fn set_pending(omegga: &Omegga, id: impl ToString, pending: &Pending) {
+    omegga.store_set(
+        format!("pending:{}", id.to_string()),
+        serde_json::to_value(pending).unwrap_or_default(),
+    );
+}
+
+/// Get where an owner's microbricks were last seen, if they've ever been flagged.
+pub async fn get_sighting(omegga: &Omegga, id: impl ToString) -> Result<Option<Sighting>> {
+    let value = omegga
+        .store_get(format!("sighting:{}", id.to_string()))
+        .await?;
+
+    Ok(match value {
+        Some(value @ Value::Object(_)) => Some(serde_json::from_value(value)?),
+        _ => None,
+    })
+}
+
+/// Set where an owner's microbricks were last seen.
+pub fn set_sighting(omegga: &Omegga, id: impl ToString, sighting: &Sighting) {
+    omegga.store_set(
+        format!("sighting:{}", id.to_string()),
+        serde_json::to_value(sighting).unwrap_or_default(),
+    );
+}
+
+/// Whether or not the scan that last flagged an owner found their bricks mostly hidden,
+/// which cuts their timer down to `hidden-clear-after-minutes`.
+///
+/// Kept by timer id, like pending clears.
+pub async fn is_hidden(omegga: &Omegga, timer: impl ToString) -> Result<bool> {
+    Ok(matches!(
+        omegga
+            .store_get(format!("hidden:{}", timer.to_string()))
+            .await?,
+        Some(Value::Bool(true))
+    ))
+}
+
+/// Record whether or not an owner's bricks were mostly hidden, by timer id.
+pub async fn set_hidden(omegga: &Omegga, timer: impl ToString, hidden: bool) {
+    let key = format!("hidden:{}", timer.to_string());
+    if hidden {
+        omegga.store_set(key, Value::Bool(true));
+    } else {
+        omegga.store_delete(key).await;
+    }
+}
+
+/// Get a timestamp record, such as `ts:{id}`, stored as a string.
+pub async fn get_timestamp(omegga: &Omegga, key: impl Into<String>) -> Result<Option<u64>> {
+    Ok(match omegga.store_get(key).await? {
+        Some(Value::String(s)) => Some(s.parse()?),
+        _ => None,
+    })
+}
+
+/// Get a count record, such as `bans:{id}`, defaulting to 0.
+pub async fn get_count(omegga: &Omegga, key: impl Into<String>) -> Result<i64> {
+    Ok(omegga
+        .store_get(key)
+        .await?
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0))
+}
+
+/// Remember a player's name, so they can be looked up by name while offline.
+pub fn set_name(omegga: &Omegga, id: impl ToString, name: &str) {
+    omegga.store_set(
+        format!("name:{}", id.to_string()),
+        Value::String(name.to_string()),
+    );
+}
+
+/// Get a player's last known name.
+pub async fn get_name(omegga: &Omegga, id: impl ToString) -> Result<Option<String>> {
+    Ok(
+        match omegga.store_get(format!("name:{}", id.to_string())).await? {
+            Some(Value::String(name)) => Some(name),
+            _ => None,
+        },
+    )
+}
+
+/// Find players by their last known name, ignoring case, as `(id, name)` pairs.
+///
+/// An exact match is returned alone, otherwise every name starting with `query` is.
+pub async fn find_by_name(omegga: &Omegga, query: &str) -> Result<Vec<(String, String)>> {
+    let query = query.to_lowercase();
+    let mut found = vec![];
+    for key in omegga.store_keys().await? {
+        let id = match key.strip_prefix("name:") {
+            Some(id) => id,
+            None => continue,
+        };
+
+        if let Some(Value::String(known)) = omegga.store_get(key.clone()).await? {
+            let lower = known.to_lowercase();
+            if lower == query {
+                return Ok(vec![(id.to_string(), known)]);
+            }
+
+            if lower.starts_with(&query) {
+                found.push((id.to_string(), known));
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Remember that a player was just seen, joining or in a scan.
+pub fn set_last_seen(omegga: &Omegga, id: impl ToString) {
+    omegga.store_set(
+        format!("lastseen:{}", id.to_string()),
+        Value::String(Utc::now().timestamp().to_string()),
+    );
+}
+
+/// Get everyone exempted with `/am exempt`, along with their name when they were exempted.
+pub async fn get_exempt(omegga: &Omegga) -> Result<HashMap<Uuid, String>> {
+    let mut exempt = HashMap::new();
+    for key in omegga.store_keys().await? {
+        let id: Uuid = match key.strip_prefix("exempt:").map(str::parse) {
+            Some(Ok(id)) => id,
+            _ => continue,
+        };
+
+        let name = match omegga.store_get(key.clone()).await? {
+            Some(Value::String(name)) => name,
+            _ => id.to_string(),
+        };
+        exempt.insert(id, name);
+    }
+
+    Ok(exempt)
+}
+
+/// Get everyone watched with `/am watch`, along with their name when they were watched.
+pub async fn get_watched(omegga: &Omegga) -> Result<HashMap<Uuid, String>> {
+    let mut watched = HashMap::new();
+    for key in omegga.store_keys().await? {
+        let id: Uuid = match key.strip_prefix("watch:").map(str::parse) {
+            Some(Ok(id)) => id,
+            _ => continue,
+        };
+
+        let name = match omegga.store_get(key.clone()).await? {
+            Some(Value::String(name)) => name,
+            _ => id.to_string(),
+        };
+        watched.insert(id, name);
+    }
+
+    Ok(watched)
+}
+
+/// Get the most recent clears, oldest first.
+pub async fn get_recent_clears(omegga: &Omegga) -> Result<Vec<RecentClear>> {
+    Ok(match omegga.store_get("recent-clears").await? {
+        Some(value @ Value::Array(_)) => serde_json::from_value(value)?,
+        _ => vec![],
+    })
+}
+
+/// Set the most recent clears.
+pub fn set_recent_clears(omegga: &Omegga, clears: &[RecentClear]) {
+    omegga.store_set(
+        "recent-clears",
+        serde_json::to_value(clears).unwrap_or_default(),
+    );
+}
+
+/// Update the most recent clears in place, keeping only the last `RECENT_CLEARS`.
+pub async fn update_recent_clears(
+    omegga: &Omegga,
+    update: impl FnOnce(&mut Vec<RecentClear>),
+) -> Result<()> {
+    let mut clears = get_recent_clears(omegga).await?;
+    update(&mut clears);
+    clears.drain(..clears.len().saturating_sub(RECENT_CLEARS));
+    set_recent_clears(omegga, &clears);
+    Ok(())
+}
+
+/// Get the logged enforcement actions, oldest first.
+pub async fn get_actions(omegga: &Omegga) -> Result<Vec<Action>> {
+    Ok(match omegga.store_get("actions").await? {
+        Some(value @ Value::Array(_)) => serde_json::from_value(value)?,
+        _ => vec![],
+    })
+}
+
+/// Log an enforcement action, keeping only the last `ACTION_LOG_SIZE`.
+pub async fn log_action(omegga: &Omegga, action: Action) -> Result<()> {
+    let mut actions = get_actions(omegga).await?;
+    actions.push(action);
+    actions.drain(..actions.len().saturating_sub(ACTION_LOG_SIZE));
+    omegga.store_set("actions", serde_json::to_value(actions).unwrap_or_default());
+    Ok(())
+}
+
+/// Get everyone with a permit from `/am permit`, along with when it expires, as a unix timestamp.
+///
+/// Expired permits are included until the end of the next scan removes them.
+pub async fn get_permits(omegga: &Omegga) -> Result<HashMap<Uuid, u64>> {
+    let mut permits = HashMap::new();
+    for key in omegga.store_keys().await? {
+        let id: Uuid = match key.strip_prefix("permit:").map(str::parse) {
+            Some(Ok(id)) => id,
+            _ => continue,
+        };
+
+        if let Some(expires) = get_timestamp(omegga, key.clone()).await? {
+            permits.insert(id, expires);
+        }
+    }
+
+    Ok(permits)
+}
+
+/// Get the enforcement stats.
+pub async fn get_stats(omegga: &Omegga) -> Result<Stats> {
+    Ok(match omegga.store_get("stats").await? {
+        Some(value @ Value::Object(_)) => serde_json::from_value(value)?,
+        _ => Stats::default(),
+    })
+}
+
+/// Update the enforcement stats in place.
+pub async fn update_stats(omegga: &Omegga, update: impl FnOnce(&mut Stats)) -> Result<()> {
+    let mut stats = get_stats(omegga).await?;
+    update(&mut stats);
+    omegga.store_set("stats", serde_json::to_value(&stats)?);
+    Ok(())
+}
+
+/// Get the assets allowed with `/am allowasset`.
+pub async fn get_allowed_assets(omegga: &Omegga) -> Result<Vec<String>> {
+    Ok(match omegga.store_get("allowed-assets").await? {
+        Some(value @ Value::Array(_)) => serde_json::from_value(value)?,
+        _ => vec![],
+    })
+}
+
+/// Set the assets allowed with `/am allowasset`.
+pub fn set_allowed_assets(omegga: &Omegga, assets: &[String]) {
+    omegga.store_set(
+        "allowed-assets",
+        serde_json::to_value(assets).unwrap_or_default(),
+    );
+}
+
+/// Get the settings overridden with `/am threshold`.
+pub async fn get_thresholds(omegga: &Omegga) -> Result<BTreeMap<String, f64>> {
+    Ok(match omegga.store_get("thresholds").await? {
+        Some(value @ Value::Object(_)) => serde_json::from_value(value)?,
+        _ => BTreeMap::new(),
+    })
+}
+
+/// Set the settings overridden with `/am threshold`.
+pub fn set_thresholds(omegga: &Omegga, thresholds: &BTreeMap<String, f64>) {
+    omegga.store_set(
+        "thresholds",
+        serde_json::to_value(thresholds).unwrap_or_default(),
+    );
+}
+
+/// Whether or not dry-run mode is on with `/am dryrun on`.
+pub async fn is_dry_run(omegga: &Omegga) -> Result<bool> {
+    Ok(matches!(
+        omegga.store_get("dryrun").await?,
+        Some(Value::Bool(true))
+    ))
+}
+
+/// Turn dry-run mode on or off.
+pub async fn set_dry_run(omegga: &Omegga, dry_run: bool) {
+    if dry_run {
+        omegga.store_set("dryrun", Value::Bool(true));
+    } else {
+        omegga.store_delete("dryrun").await;
+    }
+}
+
+/// Whether or not enforcement is paused with `/am pause`.
+pub async fn is_paused(omegga: &Omegga) -> Result<bool> {
+    Ok(matches!(
+        omegga.store_get("paused").await?,
+        Some(Value::Bool(true))
+    ))
+}
+
+/// Pause or resume enforcement.
+pub async fn set_paused(omegga: &Omegga, paused: bool) {
+    if paused {
+        omegga.store_set("paused", Value::Bool(true));
+    } else {
+        omegga.store_delete("paused").await;
+    }
+}
+
+/// Whether or not an admin wants notifications, which they do unless they ran `/am notify off`.
+pub async fn wants_notifications(omegga: &Omegga, id: impl ToString) -> Result<bool> {
+    Ok(!matches!(
+        omegga
+            .store_get(format!("notify:{}", id.to_string()))
+            .await?,
+        Some(Value::Bool(false))
+    ))
+}
+
+/// Turn an admin's notifications on or off.
+pub async fn set_notifications(omegga: &Omegga, id: impl ToString, on: bool) {
+    let key = format!("notify:{}", id.to_string());
+    if on {
+        omegga.store_delete(key).await;
+    } else {
+        omegga.store_set(key, Value::Bool(false));
+    }
+}